@@ -0,0 +1,54 @@
+//! Compares `get_version` against a full `toml_edit` parse on a large
+//! generated manifest. Run with `cargo bench --bench get_version`.
+
+use cargo_next::get_version;
+use std::{fmt::Write, fs, hint::black_box, path::Path, time::Instant};
+use toml_edit::Document;
+
+const ITERATIONS: u32 = 200;
+const DEPENDENCIES: usize = 20_000;
+
+/// Builds a manifest in the style of a generated workspace with a huge
+/// dependency table.
+fn large_manifest() -> String {
+    let mut content = String::from("[package]\nname = \"large\"\nversion = \"1.2.3\"\n\n");
+    content.push_str("[dependencies]\n");
+    for i in 0..DEPENDENCIES {
+        // Writing into a `String` never fails.
+        let _ = writeln!(
+            content,
+            "dep-{i} = {{ version = \"0.{i}.0\", features = [\"a\", \"b\"], optional = true }}"
+        );
+    }
+    content
+}
+
+/// Reads the version the way `get_version` did before it had a fast path.
+fn full_parse(path: &Path) -> String {
+    let content = fs::read_to_string(path).unwrap();
+    let doc = content.parse::<Document>().unwrap();
+    doc["package"]["version"].as_str().unwrap().to_string()
+}
+
+fn measure(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iteration = start.elapsed() / ITERATIONS;
+    println!("{name:<12} {per_iteration:>12.2?} per iteration");
+}
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Cargo.toml");
+    fs::write(&path, large_manifest()).unwrap();
+    assert_eq!(get_version(&path).unwrap().to_string(), full_parse(&path));
+
+    measure("get_version", || {
+        black_box(get_version(black_box(&path)).unwrap());
+    });
+    measure("full_parse", || {
+        black_box(full_parse(black_box(&path)));
+    });
+}
@@ -0,0 +1,126 @@
+use std::{
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A source of the current time. Exists so that tests can inject a fixed
+/// point in time.
+pub trait Clock {
+    /// Returns the current UTC date and time.
+    fn now(&self) -> DateTime;
+}
+
+/// A clock that reads the time of the operating system.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        DateTime::from_unix(secs)
+    }
+}
+
+/// A clock that always returns the same point in time.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FixedClock(pub DateTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime {
+        self.0
+    }
+}
+
+/// A UTC date and time with second precision.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    /// Converts seconds since the unix epoch into a calendar date and time.
+    pub fn from_unix(secs: u64) -> Self {
+        let days = (secs / 86_400) as i64;
+        let rem = (secs % 86_400) as u32;
+
+        // Howard Hinnant's `civil_from_days` algorithm.
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: rem / 3_600,
+            minute: rem % 3_600 / 60,
+            second: rem % 60,
+        }
+    }
+
+    /// Formats the date and time using `strftime`-like specifiers.
+    ///
+    /// Supported are `%Y`, `%m`, `%d`, `%H`, `%M`, `%S` and `%%`. Any other
+    /// specifier is kept as-is.
+    pub fn format(&self, fmt: &str) -> String {
+        let mut out = String::with_capacity(fmt.len());
+        let mut chars = fmt.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            // Writing into a `String` never fails.
+            let _ = match chars.next() {
+                Some('Y') => write!(out, "{:04}", self.year),
+                Some('m') => write!(out, "{:02}", self.month),
+                Some('d') => write!(out, "{:02}", self.day),
+                Some('H') => write!(out, "{:02}", self.hour),
+                Some('M') => write!(out, "{:02}", self.minute),
+                Some('S') => write!(out, "{:02}", self.second),
+                Some('%') => write!(out, "%"),
+                Some(other) => write!(out, "%{other}"),
+                None => write!(out, "%"),
+            };
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::DateTime;
+
+    #[test]
+    fn test_from_unix() {
+        let dt = DateTime::from_unix(0);
+        assert_eq!((dt.year, dt.month, dt.day), (1970, 1, 1));
+        let dt = DateTime::from_unix(1_714_564_800);
+        assert_eq!((dt.year, dt.month, dt.day), (2024, 5, 1));
+        assert_eq!((dt.hour, dt.minute, dt.second), (12, 0, 0));
+        let dt = DateTime::from_unix(951_782_400);
+        assert_eq!((dt.year, dt.month, dt.day), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_format() {
+        let dt = DateTime::from_unix(1_714_564_805);
+        assert_eq!(dt.format("%Y-%m-%d"), "2024-05-01");
+        assert_eq!(dt.format("%d.%m.%Y %H:%M:%S"), "01.05.2024 12:00:05");
+        assert_eq!(dt.format("100%% %q"), "100% %q");
+    }
+}
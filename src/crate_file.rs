@@ -0,0 +1,94 @@
+use crate::{parse_manifest_version, Error};
+use flate2::read::GzDecoder;
+use semver::Version;
+use std::{fs::File, io::Read, path::Path};
+
+/// Returns the version of a packaged `.crate` file, e.g. one downloaded from a
+/// registry.
+///
+/// A `.crate` file is a gzipped tarball whose entries live in a single
+/// `<name>-<version>` directory. The version is read from the `Cargo.toml`
+/// file inside of that directory, not from the file name.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `.crate` file.
+///
+/// # Returns
+///
+/// The version or [`Error::NoManifestInCrateFile`] if the archive has no
+/// top-level `Cargo.toml` file.
+pub fn version_in_crate_file(path: impl AsRef<Path>) -> Result<Version, Error> {
+    let path = path.as_ref();
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?;
+        if entry_path.components().count() == 2 && entry_path.ends_with("Cargo.toml") {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            return parse_manifest_version(&content);
+        }
+    }
+    Err(Error::NoManifestInCrateFile {
+        path: path.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crate_file::version_in_crate_file, Error};
+    use flate2::{write::GzEncoder, Compression};
+    use std::{fs::File, path::Path};
+
+    /// Packages files into a gzipped tarball like `cargo package` does.
+    fn create_crate_file(path: &Path, files: &[(&str, &str)]) {
+        let encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_version_in_crate_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("x-1.2.3.crate");
+        create_crate_file(
+            &path,
+            &[
+                ("x-1.2.3/src/lib.rs", ""),
+                (
+                    "x-1.2.3/examples/demo/Cargo.toml",
+                    "[package]\nversion = \"0.1.0\"\n",
+                ),
+                (
+                    "x-1.2.3/Cargo.toml.orig",
+                    "[package]\nversion = \"9.9.9\"\n",
+                ),
+                (
+                    "x-1.2.3/Cargo.toml",
+                    "[package]\nname = \"x\"\nversion = \"1.2.3-rc.1\"\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            version_in_crate_file(&path).unwrap().to_string(),
+            "1.2.3-rc.1"
+        );
+
+        let path = dir.path().join("empty.crate");
+        create_crate_file(&path, &[("x-1.2.3/src/lib.rs", "")]);
+        assert!(matches!(
+            version_in_crate_file(&path),
+            Err(Error::NoManifestInCrateFile { .. })
+        ));
+    }
+}
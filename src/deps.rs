@@ -0,0 +1,396 @@
+use crate::{set_version_value, write_manifest, Error};
+use semver::{BuildMetadata, Op, Version, VersionReq};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Document, Item, Value};
+
+/// The directories whose sub-crates get updated by [`update_examples`].
+const EXAMPLE_DIRS: &[&str] = &["examples", "benches"];
+
+/// The sections of a manifest that dependencies can be declared in.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DependencyKind {
+    /// The `[dependencies]` section.
+    Normal,
+    /// The `[dev-dependencies]` section.
+    Dev,
+    /// The `[build-dependencies]` section.
+    Build,
+}
+
+impl DependencyKind {
+    /// All dependency sections.
+    pub const ALL: [DependencyKind; 3] = [Self::Normal, Self::Dev, Self::Build];
+
+    /// Returns the name of the manifest table of the section.
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            Self::Normal => "dependencies",
+            Self::Dev => "dev-dependencies",
+            Self::Build => "build-dependencies",
+        }
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.table_name())
+    }
+}
+
+/// Returns the version requirement of a dependency inside a `Cargo.toml`
+/// file.
+///
+/// The dependency can be declared as a bare string (`dep = "1.2"`), an inline
+/// table (`dep = { version = "1.2" }`) or a table (`[dependencies.dep]`).
+/// Renamed dependencies are found by their key as well as their package name.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `name`: The name of the dependency.
+/// - `kind`: The section the dependency is declared in.
+///
+/// # Returns
+///
+/// The requirement string, [`Error::DependencyNotFound`] if the dependency is
+/// not declared in the section, or [`Error::InvalidFieldType`] if it has no
+/// version requirement, e.g. because it is a path-only dependency.
+pub fn get_dependency_requirement(
+    path: impl AsRef<Path>,
+    name: &str,
+    kind: DependencyKind,
+) -> Result<String, Error> {
+    let cargo_toml_content = fs::read_to_string(path.as_ref())?;
+    let doc = cargo_toml_content.parse::<Document>()?;
+
+    let entry = doc
+        .get(kind.table_name())
+        .and_then(Item::as_table_like)
+        .and_then(|deps| {
+            // Renamed dependencies can be looked up by either of their names.
+            deps.iter()
+                .find(|(key, entry)| *key == name || is_dependency_on(key, entry, name))
+        })
+        .map(|(_, entry)| entry)
+        .ok_or_else(|| Error::DependencyNotFound {
+            name: name.to_string(),
+            section: kind.table_name().to_string(),
+        })?;
+
+    requirement_value(entry)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| Error::InvalidFieldType {
+            field: format!("{kind}.{name}.version"),
+            ty: "string".to_string(),
+        })
+}
+
+/// Updates the version requirement of every dependency on a crate inside a
+/// `Cargo.toml` file, including target-specific dependencies.
+///
+/// Only requirements made of a single `=`, `^`, `~` or `>=` comparator get
+/// updated. Their operator is kept, e.g. `^1.2` becomes `^1.3.0`. Ranges and
+/// wildcards are left untouched.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `name`: The name of the crate that is depended on.
+/// - `version`: The new version of the crate.
+///
+/// # Returns
+///
+/// The sections in which a requirement changed. The file is only written if
+/// this is not empty.
+pub fn update_dependency_requirement(
+    path: impl AsRef<Path>,
+    name: &str,
+    version: &Version,
+) -> Result<Vec<DependencyKind>, Error> {
+    update_dependency_requirement_in(path, name, version, &DependencyKind::ALL)
+}
+
+/// Updates the version requirements on a crate like
+/// [`update_dependency_requirement`], but only inside the given sections.
+pub fn update_dependency_requirement_in(
+    path: impl AsRef<Path>,
+    name: &str,
+    version: &Version,
+    kinds: &[DependencyKind],
+) -> Result<Vec<DependencyKind>, Error> {
+    let cargo_toml_content = fs::read_to_string(path.as_ref())?;
+    let mut doc = cargo_toml_content.parse::<Document>()?;
+
+    let mut changed = Vec::new();
+    for &kind in kinds {
+        let mut tables = Vec::new();
+        for (key, item) in doc.as_table_mut().iter_mut() {
+            if key.get() == kind.table_name() {
+                tables.push(item);
+            } else if key.get() == "target" {
+                let Some(targets) = item.as_table_like_mut() else {
+                    continue;
+                };
+                for (_, target) in targets.iter_mut() {
+                    if let Some(deps) = target.get_mut(kind.table_name()) {
+                        tables.push(deps);
+                    }
+                }
+            }
+        }
+
+        for deps in tables {
+            let Some(deps) = deps.as_table_like_mut() else {
+                continue;
+            };
+            for (key, entry) in deps.iter_mut() {
+                if !is_dependency_on(key.get(), entry, name) {
+                    continue;
+                }
+                let Some(value) = requirement_value_mut(entry) else {
+                    continue;
+                };
+                let Some(req) = value
+                    .as_str()
+                    .and_then(|req| rewrite_requirement(req, version))
+                else {
+                    continue;
+                };
+                if value.as_str() != Some(req.as_str()) {
+                    set_version_value(value, &req);
+                    if !changed.contains(&kind) {
+                        changed.push(kind);
+                    }
+                }
+            }
+        }
+    }
+
+    if !changed.is_empty() {
+        write_manifest(path.as_ref(), &doc.to_string())?;
+    }
+    Ok(changed)
+}
+
+/// Updates the version requirements on a crate inside the sub-crates of the
+/// `examples` and `benches` directories of a project.
+///
+/// Those crates are usually not workspace members, so they are looked up as
+/// `examples/*/Cargo.toml` and `benches/*/Cargo.toml`.
+///
+/// # Arguments
+///
+/// - `root`: The directory of the crate that has been bumped.
+/// - `name`: The name of the bumped crate.
+/// - `version`: The new version of the bumped crate.
+/// - `kinds`: The dependency sections to update, e.g.
+///   [`DependencyKind::ALL`].
+///
+/// # Returns
+///
+/// The paths of all manifests that changed, each with the sections in which
+/// a requirement changed.
+pub fn update_examples(
+    root: impl AsRef<Path>,
+    name: &str,
+    version: &Version,
+    kinds: &[DependencyKind],
+) -> Result<Vec<(PathBuf, Vec<DependencyKind>)>, Error> {
+    let mut updated = Vec::new();
+    for dir in EXAMPLE_DIRS {
+        let dir = root.as_ref().join(dir);
+        if !dir.is_dir() {
+            continue;
+        }
+
+        let mut manifests = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let manifest = entry?.path().join("Cargo.toml");
+            if manifest.is_file() {
+                manifests.push(manifest);
+            }
+        }
+        manifests.sort();
+
+        for manifest in manifests {
+            let changed = update_dependency_requirement_in(&manifest, name, version, kinds)?;
+            if !changed.is_empty() {
+                updated.push((manifest, changed));
+            }
+        }
+    }
+    Ok(updated)
+}
+
+/// Returns whether a dependency entry refers to the given crate, taking
+/// renamed dependencies (`package = "..."`) into account.
+fn is_dependency_on(key: &str, entry: &Item, name: &str) -> bool {
+    let package = match entry {
+        Item::Value(Value::InlineTable(table)) => table.get("package").and_then(Value::as_str),
+        Item::Table(table) => table.get("package").and_then(Item::as_str),
+        _ => None,
+    };
+    package.unwrap_or(key) == name
+}
+
+/// Returns the value holding the version requirement of a dependency entry,
+/// which is either a bare string or the `version` key of a table.
+fn requirement_value(entry: &Item) -> Option<&Value> {
+    match entry {
+        Item::Value(value @ Value::String(_)) => Some(value),
+        Item::Value(Value::InlineTable(table)) => table.get("version"),
+        Item::Table(table) => table.get("version")?.as_value(),
+        _ => None,
+    }
+}
+
+/// The mutable counterpart of [`requirement_value`].
+fn requirement_value_mut(entry: &mut Item) -> Option<&mut Value> {
+    match entry {
+        Item::Value(value @ Value::String(_)) => Some(value),
+        Item::Value(Value::InlineTable(table)) => table.get_mut("version"),
+        Item::Table(table) => table.get_mut("version")?.as_value_mut(),
+        _ => None,
+    }
+}
+
+/// Points a single-comparator requirement to a new version while keeping its
+/// operator. Returns `None` for requirements that can't be rewritten that way.
+fn rewrite_requirement(req: &str, version: &Version) -> Option<String> {
+    let parsed = VersionReq::parse(req).ok()?;
+    let [comparator] = parsed.comparators.as_slice() else {
+        return None;
+    };
+    let op = match comparator.op {
+        Op::Exact => "=",
+        Op::Caret if req.trim_start().starts_with('^') => "^",
+        Op::Caret => "",
+        Op::Tilde => "~",
+        Op::GreaterEq => ">=",
+        _ => return None,
+    };
+    // Build metadata has no meaning inside requirements.
+    let mut version = version.clone();
+    version.build = BuildMetadata::EMPTY;
+    Some(format!("{op}{version}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        deps::{get_dependency_requirement, rewrite_requirement, update_examples, DependencyKind},
+        Error,
+    };
+    use semver::Version;
+    use std::fs;
+
+    #[test]
+    fn test_rewrite_requirement() {
+        let v = Version::parse("1.3.0").unwrap();
+        assert_eq!(rewrite_requirement("1.2.3", &v).unwrap(), "1.3.0");
+        assert_eq!(rewrite_requirement("^1.2", &v).unwrap(), "^1.3.0");
+        assert_eq!(rewrite_requirement("=1.2.3", &v).unwrap(), "=1.3.0");
+        assert_eq!(rewrite_requirement("~1.2.3", &v).unwrap(), "~1.3.0");
+        assert_eq!(rewrite_requirement(">=1.2", &v).unwrap(), ">=1.3.0");
+        assert_eq!(rewrite_requirement(">=1.2, <2", &v), None);
+        assert_eq!(rewrite_requirement("1.*", &v), None);
+        assert_eq!(rewrite_requirement("<2", &v), None);
+        let v = Version::parse("1.3.0-rc.1+abc").unwrap();
+        assert_eq!(rewrite_requirement("^1.2", &v).unwrap(), "^1.3.0-rc.1");
+    }
+
+    #[test]
+    fn test_update_examples() {
+        let dir = tempfile::tempdir().unwrap();
+        let example = dir.path().join("examples").join("demo");
+        fs::create_dir_all(&example).unwrap();
+        fs::write(
+            example.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nmain-crate = { path = \"../..\", version = \"1.2.3\" }\n\
+             other = \"1.2.3\"\n\n\
+             [dev-dependencies.renamed]\npackage = \"main-crate\"\npath = \"../..\"\n\
+             version = \"=1.2.3\" # pinned\n",
+        )
+        .unwrap();
+        let untouched = dir.path().join("benches").join("untouched");
+        fs::create_dir_all(&untouched).unwrap();
+        fs::write(
+            untouched.join("Cargo.toml"),
+            "[package]\nname = \"untouched\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        // Leaving out the dev-dependencies only touches the normal ones.
+        let v = Version::parse("1.2.4").unwrap();
+        let updated =
+            update_examples(dir.path(), "main-crate", &v, &[DependencyKind::Normal]).unwrap();
+        assert_eq!(
+            updated,
+            vec![(example.join("Cargo.toml"), vec![DependencyKind::Normal])]
+        );
+        assert!(fs::read_to_string(example.join("Cargo.toml"))
+            .unwrap()
+            .contains("version = \"=1.2.3\" # pinned"));
+
+        let v = Version::parse("1.3.0").unwrap();
+        let updated = update_examples(dir.path(), "main-crate", &v, &DependencyKind::ALL).unwrap();
+        assert_eq!(
+            updated,
+            vec![(
+                example.join("Cargo.toml"),
+                vec![DependencyKind::Normal, DependencyKind::Dev]
+            )]
+        );
+        assert_eq!(
+            fs::read_to_string(example.join("Cargo.toml")).unwrap(),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nmain-crate = { path = \"../..\", version = \"1.3.0\" }\n\
+             other = \"1.2.3\"\n\n\
+             [dev-dependencies.renamed]\npackage = \"main-crate\"\npath = \"../..\"\n\
+             version = \"=1.3.0\" # pinned\n",
+        );
+        assert!(
+            update_examples(dir.path(), "main-crate", &v, &DependencyKind::ALL)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_get_dependency_requirement() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(
+            &path,
+            "[dependencies]\nbare = \"1.2\"\ninline = { version = \"=0.3.1\", default-features = false }\n\
+             local = { path = \"../local\" }\n\n\
+             [dev-dependencies.table]\nversion = \"~2\"\n\n\
+             [build-dependencies]\nrenamed = { package = \"original\", version = \"0.1\" }\n",
+        )
+        .unwrap();
+
+        let req = |name, kind| get_dependency_requirement(&path, name, kind);
+        assert_eq!(req("bare", DependencyKind::Normal).unwrap(), "1.2");
+        assert_eq!(req("inline", DependencyKind::Normal).unwrap(), "=0.3.1");
+        assert_eq!(req("table", DependencyKind::Dev).unwrap(), "~2");
+        assert_eq!(req("original", DependencyKind::Build).unwrap(), "0.1");
+        assert_eq!(req("renamed", DependencyKind::Build).unwrap(), "0.1");
+        assert!(matches!(
+            req("table", DependencyKind::Normal),
+            Err(Error::DependencyNotFound { .. })
+        ));
+        assert!(matches!(
+            req("missing", DependencyKind::Build),
+            Err(Error::DependencyNotFound { .. })
+        ));
+        assert!(matches!(
+            req("local", DependencyKind::Normal),
+            Err(Error::InvalidFieldType { .. })
+        ));
+    }
+}
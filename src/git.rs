@@ -0,0 +1,359 @@
+use crate::{ignore::IgnoreList, Error, Increment};
+use semver::{BuildMetadata, Prerelease, Version};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use toml_edit::Document;
+
+/// Runs `git` inside a directory and returns its standard output.
+fn git(dir: &Path, args: &[&str]) -> Result<String, Error> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Error::GitNotFound,
+            _ => Error::IoError(e),
+        })?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(Error::GitFailed {
+            status: output.status,
+        })
+    }
+}
+
+/// Returns the files that changed between a base revision and the working
+/// tree of the repository a directory belongs to.
+///
+/// # Arguments
+///
+/// - `dir`: A directory inside the repository.
+/// - `base`: The revision to compare against, e.g. `origin/main`.
+///
+/// # Returns
+///
+/// The absolute paths of the changed files, including deleted ones.
+pub fn changed_files(dir: impl AsRef<Path>, base: &str) -> Result<Vec<PathBuf>, Error> {
+    let dir = dir.as_ref();
+    let toplevel = git(dir, &["rev-parse", "--show-toplevel"])?;
+    let toplevel = Path::new(toplevel.trim_end());
+    let diff = git(dir, &["diff", "--name-only", base, "--"])?;
+    Ok(parse_name_only(toplevel, &diff))
+}
+
+/// Parses the output of `git diff --name-only`, whose paths are relative to
+/// the top-level directory of the repository.
+pub fn parse_name_only(toplevel: &Path, diff: &str) -> Vec<PathBuf> {
+    diff.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| toplevel.join(line))
+        .collect()
+}
+
+/// Where the distance to the last tag ends up in a version derived from
+/// `git describe`, see [`parse_describe`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum DescribeMapping {
+    /// `v1.2.3-5-gabcdef` becomes `1.2.3-5.gabcdef`.
+    #[default]
+    Prerelease,
+    /// `v1.2.3-5-gabcdef` becomes `1.2.3+5.gabcdef`.
+    Build,
+}
+
+/// Derives a version from the output of `git describe --tags`.
+///
+/// The tag may have a leading `v`. If commits were made since the tag, their
+/// count and the abbreviated commit hash get appended to the pre-release or
+/// the build metadata, depending on the mapping:
+///
+/// | describe output           | `Prerelease`              | `Build`                   |
+/// |---------------------------|---------------------------|---------------------------|
+/// | `v1.2.3`                  | `1.2.3`                   | `1.2.3`                   |
+/// | `v1.2.3-5-gabcdef`        | `1.2.3-5.gabcdef`         | `1.2.3+5.gabcdef`         |
+/// | `v1.2.3-rc.1-5-gabcdef`   | `1.2.3-rc.1.5.gabcdef`    | `1.2.3-rc.1+5.gabcdef`    |
+/// | `v1.2.3-5-gabcdef-dirty`  | `1.2.3-5.gabcdef.dirty`   | `1.2.3+5.gabcdef.dirty`   |
+///
+/// # Returns
+///
+/// The version or [`Error::InvalidDescribe`] if the tag is no semver version.
+pub fn parse_describe(output: &str, mapping: DescribeMapping) -> Result<Version, Error> {
+    let invalid = || Error::InvalidDescribe {
+        output: output.to_string(),
+    };
+    let (rest, dirty) = match output.strip_suffix("-dirty") {
+        Some(rest) => (rest, true),
+        None => (output, false),
+    };
+    let mut parts = rest.rsplitn(3, '-');
+    let (tag, mut suffix) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(hash), Some(count), Some(tag))
+            if hash.starts_with('g') && count.parse::<u64>().is_ok() =>
+        {
+            (tag, vec![count, hash])
+        }
+        _ => (rest, Vec::new()),
+    };
+    if dirty {
+        suffix.push("dirty");
+    }
+
+    let mut version =
+        Version::parse(tag.strip_prefix('v').unwrap_or(tag)).map_err(|_| invalid())?;
+    if suffix.is_empty() {
+        return Ok(version);
+    }
+    let suffix = suffix.join(".");
+    match mapping {
+        DescribeMapping::Prerelease if version.pre.is_empty() => {
+            version.pre = Prerelease::new(&suffix).map_err(|_| invalid())?;
+        }
+        DescribeMapping::Prerelease => {
+            let pre = format!("{}.{suffix}", version.pre);
+            version.pre = Prerelease::new(&pre).map_err(|_| invalid())?;
+        }
+        DescribeMapping::Build => {
+            version.build = BuildMetadata::new(&suffix).map_err(|_| invalid())?;
+        }
+    }
+    Ok(version)
+}
+
+/// Derives a version from `git describe --tags` inside a repository, see
+/// [`parse_describe`].
+///
+/// # Returns
+///
+/// The version or [`Error::GitFailed`] if there is no tag to describe from.
+pub fn describe_version(dir: impl AsRef<Path>, mapping: DescribeMapping) -> Result<Version, Error> {
+    let output = git(dir.as_ref(), &["describe", "--tags"])?;
+    parse_describe(output.trim_end(), mapping)
+}
+
+/// Returns the full messages of the commits that are reachable from `HEAD` but
+/// not from a base revision.
+///
+/// # Arguments
+///
+/// - `dir`: A directory inside the repository.
+/// - `base`: The revision to compare against, e.g. `origin/main`.
+pub fn commit_messages(dir: impl AsRef<Path>, base: &str) -> Result<Vec<String>, Error> {
+    let log = git(
+        dir.as_ref(),
+        &["log", "--format=%B%x00", &format!("{base}..HEAD"), "--"],
+    )?;
+    Ok(log
+        .split('\0')
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Infers the increment that a set of commits implies according to the
+/// conventional commits specification.
+///
+/// A `!` after the type or a `BREAKING CHANGE:` footer implies a major
+/// increment, the type `feat` a minor and the types `fix` and `perf` a patch
+/// increment. The highest increment of all commits wins.
+///
+/// # Returns
+///
+/// The increment, or `None` if no commit is release-worthy.
+pub fn infer_increment_from_commits(messages: &[impl AsRef<str>]) -> Option<Increment> {
+    // Commits only ever imply field increments.
+    let rank = |increment: &Increment| match increment {
+        Increment::Pre(_) | Increment::Finalize => 0,
+        Increment::Patch => 1,
+        Increment::Minor => 2,
+        Increment::Major => 3,
+    };
+    messages
+        .iter()
+        .filter_map(|message| commit_increment(message.as_ref()))
+        .max_by_key(rank)
+}
+
+/// Returns the increment a single conventional commit implies.
+fn commit_increment(message: &str) -> Option<Increment> {
+    let header = message.lines().next()?;
+    let (prefix, _) = header.split_once(':')?;
+    let breaking = prefix.ends_with('!')
+        || message.lines().any(|line| {
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        });
+    let ty = prefix
+        .split(['(', '!'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+
+    match ty.as_str() {
+        _ if breaking => Some(Increment::Major),
+        "feat" => Some(Increment::Minor),
+        "fix" | "perf" => Some(Increment::Patch),
+        _ => None,
+    }
+}
+
+/// Returns the `Cargo.toml` file of the crate a file belongs to.
+///
+/// The directory of the file and its ancestors are searched for the nearest
+/// `Cargo.toml` file. The file does not have to exist anymore, which allows
+/// mapping deleted files too.
+///
+/// # Returns
+///
+/// The path to the manifest or `None` if the nearest manifest has no
+/// `[package]` table, e.g. a virtual workspace root, or if there is none.
+pub fn owning_manifest(file: impl AsRef<Path>) -> Result<Option<PathBuf>, Error> {
+    let Some(parent) = file.as_ref().parent() else {
+        return Ok(None);
+    };
+    for dir in parent.ancestors() {
+        let manifest = dir.join("Cargo.toml");
+        if manifest.is_file() {
+            let doc = fs::read_to_string(&manifest)?.parse::<Document>()?;
+            return Ok(doc.contains_key("package").then_some(manifest));
+        }
+    }
+    Ok(None)
+}
+
+/// Groups changed files by the crate they belong to.
+///
+/// Files that don't belong to any crate are left out, as well as crates whose
+/// manifest is ignored.
+///
+/// # Returns
+///
+/// The `Cargo.toml` files of the affected crates, each with the changed files
+/// that caused it to be included.
+pub fn changed_manifests(
+    files: &[impl AsRef<Path>],
+    ignore: &IgnoreList,
+) -> Result<BTreeMap<PathBuf, Vec<PathBuf>>, Error> {
+    let mut manifests: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    for file in files {
+        let manifest = owning_manifest(file)?.filter(|manifest| !ignore.is_ignored(manifest));
+        if let Some(manifest) = manifest {
+            manifests
+                .entry(manifest)
+                .or_default()
+                .push(file.as_ref().to_path_buf());
+        }
+    }
+    Ok(manifests)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        git::{
+            changed_manifests, infer_increment_from_commits, owning_manifest, parse_describe,
+            parse_name_only, DescribeMapping,
+        },
+        ignore::IgnoreList,
+        workspace::tests::create_workspace,
+        Error, Increment,
+    };
+    use std::fs;
+
+    #[test]
+    fn test_owning_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        create_workspace(dir.path(), &[("a", "1.0.0"), ("b", "1.0.0")]);
+        let a = dir.path().join("crates").join("a");
+        fs::create_dir_all(a.join("src").join("nested")).unwrap();
+
+        let manifest = a.join("Cargo.toml");
+        let source = a.join("src").join("nested").join("lib.rs");
+        assert_eq!(owning_manifest(&source).unwrap(), Some(manifest.clone()));
+        assert_eq!(owning_manifest(&manifest).unwrap(), Some(manifest.clone()));
+        // Deleted files get mapped through the directories that still exist.
+        assert_eq!(
+            owning_manifest(a.join("src").join("gone").join("mod.rs")).unwrap(),
+            Some(manifest.clone())
+        );
+        // The virtual workspace root is not a crate.
+        assert_eq!(owning_manifest(dir.path().join("README.md")).unwrap(), None);
+
+        let files = parse_name_only(
+            dir.path(),
+            "README.md\ncrates/a/src/nested/lib.rs\ncrates/a/Cargo.toml\n",
+        );
+        assert_eq!(files[0], dir.path().join("README.md"));
+        let manifests = changed_manifests(&files, &IgnoreList::default()).unwrap();
+        assert_eq!(manifests.len(), 1);
+        assert_eq!(manifests[&manifest], vec![source, manifest.clone()]);
+
+        let ignore = IgnoreList::parse(dir.path(), "crates/a").unwrap();
+        assert!(changed_manifests(&files, &ignore).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_infer_increment_from_commits() {
+        let infer = |messages: &[&str]| infer_increment_from_commits(messages);
+        assert_eq!(infer(&[]), None);
+        assert_eq!(
+            infer(&["docs: typo", "chore(ci): cache", "Merge branch"]),
+            None
+        );
+        assert_eq!(infer(&["fix: crash", "docs: typo"]), Some(Increment::Patch));
+        assert_eq!(infer(&["perf(toml): faster"]), Some(Increment::Patch));
+        assert_eq!(
+            infer(&["fix: crash", "feat(cli): flag"]),
+            Some(Increment::Minor)
+        );
+        assert_eq!(
+            infer(&["feat!: drop api", "fix: crash"]),
+            Some(Increment::Major)
+        );
+        assert_eq!(infer(&["refactor(lib)!: rename"]), Some(Increment::Major));
+        assert_eq!(
+            infer(&["feat: new api\n\nBREAKING CHANGE: the old one is gone"]),
+            Some(Increment::Major)
+        );
+    }
+
+    #[test]
+    fn test_parse_describe() {
+        let parse = |output, mapping| parse_describe(output, mapping).map(|v| v.to_string());
+        use DescribeMapping::*;
+
+        for (output, pre, build) in [
+            ("v1.2.3", "1.2.3", "1.2.3"),
+            ("1.2.3", "1.2.3", "1.2.3"),
+            ("v1.2.3-5-gabcdef", "1.2.3-5.gabcdef", "1.2.3+5.gabcdef"),
+            ("v1.2.3-rc.1", "1.2.3-rc.1", "1.2.3-rc.1"),
+            (
+                "v1.2.3-rc.1-5-gabcdef",
+                "1.2.3-rc.1.5.gabcdef",
+                "1.2.3-rc.1+5.gabcdef",
+            ),
+            (
+                "v1.2.3-5-gabcdef-dirty",
+                "1.2.3-5.gabcdef.dirty",
+                "1.2.3+5.gabcdef.dirty",
+            ),
+            ("v1.2.3-dirty", "1.2.3-dirty", "1.2.3+dirty"),
+        ] {
+            assert_eq!(parse(output, Prerelease).unwrap(), pre, "{output}");
+            assert_eq!(parse(output, Build).unwrap(), build, "{output}");
+        }
+        assert!(matches!(
+            parse("release-5-gabcdef", Prerelease),
+            Err(Error::InvalidDescribe { .. })
+        ));
+    }
+}
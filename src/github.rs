@@ -0,0 +1,63 @@
+use crate::Error;
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+/// The environment variable that GitHub Actions sets to the path of the file
+/// collecting the outputs of a step.
+pub const GITHUB_OUTPUT: &str = "GITHUB_OUTPUT";
+
+/// Renders step outputs in the multiline-safe format of `$GITHUB_OUTPUT`, e.g.
+/// `version<<EOF` followed by the value and the delimiter on separate lines.
+///
+/// The delimiter gets extended until no value contains it.
+pub fn format_github_output(outputs: &[(&str, &str)]) -> String {
+    let mut delimiter = "CARGO_NEXT_EOF".to_string();
+    while outputs.iter().any(|(_, value)| value.contains(&delimiter)) {
+        delimiter.push('_');
+    }
+    outputs
+        .iter()
+        .map(|(name, value)| format!("{name}<<{delimiter}\n{value}\n{delimiter}\n"))
+        .collect()
+}
+
+/// Appends step outputs to the file that `$GITHUB_OUTPUT` points to, see
+/// [`format_github_output`].
+///
+/// # Arguments
+///
+/// - `path`: The path to the output file.
+/// - `outputs`: The names and values of the outputs.
+///
+/// # Returns
+///
+/// An error if the file could not be written.
+pub fn append_github_output(path: impl AsRef<Path>, outputs: &[(&str, &str)]) -> Result<(), Error> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format_github_output(outputs).as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::github::{append_github_output, format_github_output};
+    use std::fs;
+
+    #[test]
+    fn test_github_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("output");
+        fs::write(&path, "existing=1\n").unwrap();
+
+        append_github_output(&path, &[("version", "1.2.4"), ("old_version", "1.2.3")]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "existing=1\n\
+             version<<CARGO_NEXT_EOF\n1.2.4\nCARGO_NEXT_EOF\n\
+             old_version<<CARGO_NEXT_EOF\n1.2.3\nCARGO_NEXT_EOF\n"
+        );
+        assert_eq!(
+            format_github_output(&[("a", "x\nCARGO_NEXT_EOF")]),
+            "a<<CARGO_NEXT_EOF_\nx\nCARGO_NEXT_EOF\nCARGO_NEXT_EOF_\n"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::Error;
+use glob::Pattern;
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file listing the manifests that discovery skips.
+pub const IGNORE_FILE: &str = ".cargo-next-ignore";
+
+/// The patterns of a `.cargo-next-ignore` file.
+///
+/// Every line is a glob that is matched against the paths relative to the
+/// directory of the file, e.g. `vendor/**`. A pattern that matches a
+/// directory ignores all manifests inside of it. Empty lines and lines
+/// starting with `#` are skipped. Negations are not supported.
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreList {
+    base: PathBuf,
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreList {
+    /// Parses the content of an ignore file whose patterns are relative to
+    /// `base`.
+    pub fn parse(base: impl AsRef<Path>, content: &str) -> Result<Self, Error> {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let line = line.trim_start_matches('/').trim_end_matches('/');
+                Pattern::new(line).map_err(|e| Error::InvalidPattern {
+                    pattern: format!("{line}: {}", e.msg),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            base: base.as_ref().to_path_buf(),
+            patterns,
+        })
+    }
+
+    /// Loads the ignore file closest to a directory, searching the directory
+    /// and its ancestors.
+    ///
+    /// # Returns
+    ///
+    /// The patterns of the file, which are empty if there is none.
+    pub fn find(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        for dir in dir.as_ref().ancestors() {
+            match fs::read_to_string(dir.join(IGNORE_FILE)) {
+                Ok(content) => return Self::parse(dir, &content),
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Returns whether a path or one of the directories it is in matches a
+    /// pattern. Paths outside of the base directory are never ignored.
+    pub fn is_ignored(&self, path: impl AsRef<Path>) -> bool {
+        let Ok(relative) = path.as_ref().strip_prefix(&self.base) else {
+            return false;
+        };
+        relative
+            .ancestors()
+            .filter(|path| !path.as_os_str().is_empty())
+            .any(|path| self.patterns.iter().any(|p| p.matches_path(path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ignore::IgnoreList;
+    use std::path::Path;
+
+    #[test]
+    fn test_is_ignored() {
+        let ignore =
+            IgnoreList::parse("/repo", "# vendored crates\nvendor/**\n\n/fixtures/\n").unwrap();
+        assert!(ignore.is_ignored("/repo/vendor/dep/Cargo.toml"));
+        assert!(ignore.is_ignored("/repo/fixtures/nested/Cargo.toml"));
+        assert!(!ignore.is_ignored("/repo/crates/a/Cargo.toml"));
+        assert!(!ignore.is_ignored("/repo/crates/vendor/Cargo.toml"));
+        assert!(!ignore.is_ignored("/elsewhere/vendor/dep/Cargo.toml"));
+        assert!(!IgnoreList::default().is_ignored(Path::new("vendor/Cargo.toml")));
+        assert!(IgnoreList::parse("/repo", "[").is_err());
+    }
+}
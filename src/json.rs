@@ -0,0 +1,55 @@
+//! Minimal helpers for rendering JSON output without pulling in a serializer.
+
+use std::fmt::Write;
+
+/// Renders a string as a JSON string literal.
+pub(crate) fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                // Writing into a `String` never fails.
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Renders an optional string as a JSON string literal or `null`.
+pub(crate) fn opt_string(s: Option<&str>) -> String {
+    s.map(string).unwrap_or_else(|| "null".to_string())
+}
+
+/// Renders key-value pairs whose values are already rendered as a JSON
+/// object.
+pub(crate) fn object<'a>(fields: impl IntoIterator<Item = (&'a str, String)>) -> String {
+    let fields: Vec<_> = fields
+        .into_iter()
+        .map(|(key, value)| format!("{}:{value}", string(key)))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{object, opt_string, string};
+
+    #[test]
+    fn test_json() {
+        assert_eq!(string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+        assert_eq!(opt_string(None), "null");
+        assert_eq!(
+            object([("a", string("x")), ("b", "1".to_string())]),
+            "{\"a\":\"x\",\"b\":1}"
+        );
+    }
+}
@@ -1,7 +1,32 @@
-use semver::Version;
-use std::{fs, io::Error as IoError, path::Path};
+use crate::clock::Clock;
+use semver::{BuildMetadata, Prerelease, Version, VersionReq};
+use std::{
+    cmp::Ordering,
+    env, fmt, fs,
+    io::{Error as IoError, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
 use thiserror::Error;
-use toml_edit::{value, Document, Item, TomlError};
+use toml_edit::{value, Document, Item, Table, TomlError, Value};
+
+pub mod clock;
+#[cfg(feature = "crate-file")]
+pub mod crate_file;
+pub mod deps;
+pub mod git;
+pub mod github;
+pub mod ignore;
+mod json;
+#[cfg(feature = "metadata")]
+pub mod metadata;
+#[cfg(feature = "registry")]
+pub mod registry;
+pub mod workspace;
+
+/// The name of the table below `[package.metadata]` that release information
+/// gets recorded in.
+const METADATA_TABLE: &str = "cargo-next";
 
 /// The error type of this crate.
 #[derive(Debug, Error)]
@@ -20,10 +45,114 @@ pub enum Error {
     /// right type (String).
     #[error("the field {field:?} is not of type {ty:?}")]
     InvalidFieldType { field: String, ty: String },
+    /// An error that gets emitted if a pre-release is advanced but the version
+    /// has no pre-release ending in a numeric counter.
+    #[error("the version {version:?} has no numeric pre-release counter")]
+    NoPrereleaseCounter { version: String },
+    /// An error that gets emitted if a pre-release is bumped with another
+    /// label than the one of the existing pre-release.
+    #[error("the pre-release {actual:?} does not have the label {expected:?}")]
+    PrereleaseLabelMismatch { expected: String, actual: String },
+    /// An error that gets emitted if the version `0.0.0` would be written,
+    /// which almost always indicates empty input or a failed computation.
+    #[error("refusing to write the version 0.0.0, allow it explicitly if this is intended")]
+    ZeroVersion,
+    /// An error that gets emitted if a version should be read from stdin, but
+    /// the input is empty.
+    #[error("no version was given, pass one as an argument or pipe it into stdin")]
+    NoVersionGiven,
+    /// An error that gets emitted if a version lower than the current one
+    /// would be written while downgrades are forbidden.
+    #[error("refusing to downgrade from {from} to {to}")]
+    Downgrade { from: Version, to: Version },
+    /// An error that gets emitted if a version below the configured minimum
+    /// would be written.
+    #[error("refusing to write {version}, which is below the minimum version {min}")]
+    BelowMinimum { min: Version, version: Version },
+    /// An error that gets emitted if a dependency is not declared in the
+    /// requested section.
+    #[error("the dependency {name:?} was not found in {section:?}")]
+    DependencyNotFound { name: String, section: String },
+    /// An error that gets emitted if `cargo` could not be found on the `PATH`.
+    #[error("cargo could not be found, make sure it is on your PATH")]
+    CargoNotFound,
+    /// An error that gets emitted if a `cargo` invocation failed.
+    #[error("cargo exited with {status}")]
+    CargoFailed { status: ExitStatus },
+    /// An error that gets emitted if a template contains a placeholder that is
+    /// not known.
+    #[error("unknown placeholder {{{name}}} in the template")]
+    UnknownPlaceholder { name: String },
+    /// An error that gets emitted if a `Cargo.toml` file can't be written
+    /// because it is read-only.
+    #[error("{path:?} is read-only, check its permissions")]
+    ReadOnlyManifest { path: PathBuf },
+    /// An error that gets emitted if no editor is configured through `$EDITOR`.
+    #[error("$EDITOR is not set")]
+    EditorNotSet,
+    /// An error that gets emitted if the editor exited unsuccessfully.
+    #[error("the editor exited with {status}")]
+    EditorFailed { status: ExitStatus },
+    /// An error that gets emitted if outputs are required for GitHub Actions,
+    /// but `$GITHUB_OUTPUT` is not set.
+    #[error("$GITHUB_OUTPUT is not set, make sure to run inside GitHub Actions")]
+    GithubOutputNotSet,
+    /// An error that gets emitted if the output of `git describe` does not
+    /// start with a semver tag.
+    #[error("{output:?} can't be turned into a semver version")]
+    InvalidDescribe { output: String },
+    /// An error that gets emitted if `git` could not be found on the `PATH`.
+    #[error("git could not be found, make sure it is on your PATH")]
+    GitNotFound,
+    /// An error that gets emitted if a `git` invocation failed.
+    #[error("git exited with {status}")]
+    GitFailed { status: ExitStatus },
+    /// An error that gets emitted if a version contains a template placeholder
+    /// like `${VERSION}` that has not been expanded yet.
+    #[error("the version {value:?} contains a template placeholder that has not been expanded")]
+    TemplatePlaceholder { value: String },
+    /// An error that gets emitted if no workspace root could be found above a
+    /// manifest.
+    #[error("no workspace root was found above {path:?}")]
+    WorkspaceRootNotFound { path: PathBuf },
+    /// An error that gets emitted if a package is not a member of the
+    /// workspace.
+    #[error("the package {name:?} was not found in the workspace")]
+    PackageNotFound { name: String },
+    /// An error that gets emitted if a `Cargo.toml` file belongs to another
+    /// package than expected.
+    #[error("expected the package {expected:?}, but found {actual:?}")]
+    UnexpectedPackageName { expected: String, actual: String },
+    /// An error that gets emitted for an operation on several packages that
+    /// got none.
+    #[error("no packages were given")]
+    NoPackages,
+    /// An error that gets emitted if a glob pattern is invalid.
+    #[error("the pattern {pattern:?} is invalid")]
+    InvalidPattern { pattern: String },
+    /// An error that gets emitted if a `.crate` file does not contain a
+    /// `Cargo.toml` file.
+    #[cfg(feature = "crate-file")]
+    #[error("{path:?} does not contain a Cargo.toml file")]
+    NoManifestInCrateFile { path: PathBuf },
+    /// An error that gets emitted if the output of `cargo metadata` could not
+    /// be understood.
+    #[cfg(feature = "metadata")]
+    #[error("the cargo metadata is invalid: {0}")]
+    InvalidMetadata(String),
+    /// An error that gets emitted if the output of `cargo metadata` does not
+    /// list a package.
+    #[cfg(feature = "metadata")]
+    #[error("the package {name:?} was not found in the cargo metadata")]
+    PackageNotInMetadata { name: String },
+    /// An error that occurred while querying a crate registry.
+    #[cfg(feature = "registry")]
+    #[error("the registry request failed: {0}")]
+    Registry(String),
 }
 
 /// An enum defining what types of increments can be done to a semver version.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Increment {
     /// A major increment.
     Major,
@@ -31,16 +160,274 @@ pub enum Increment {
     Minor,
     /// A patch increment.
     Patch,
+    /// A pre-release increment with the given label, see
+    /// [`bump_prerelease`].
+    Pre(String),
+    /// Removes the pre-release, see [`finalize_prerelease`].
+    Finalize,
 }
 
-pub fn get_package_version_str(path: impl AsRef<Path>) -> Result<String, Error> {
+/// Options that control how a `Cargo.toml` file gets written.
+///
+/// By default, the formatting of the original file is preserved.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct WriteOptions {
+    /// Normalizes all line endings to LF and ends the file with exactly one
+    /// newline.
+    pub normalize_output: bool,
+    /// Allows writing the version `0.0.0`, which is refused otherwise.
+    pub allow_zero: bool,
+    /// Refuses to write a version that is lower than the current one by
+    /// semver precedence.
+    pub forbid_downgrade: bool,
+    /// Exempts reopening a pre-release from [`forbid_downgrade`], see
+    /// [`is_reopened_prerelease`].
+    ///
+    /// [`forbid_downgrade`]: WriteOptions::forbid_downgrade
+    pub allow_prerelease_downgrade: bool,
+    /// Refuses to write a version lower than this floor by semver precedence,
+    /// regardless of the current version.
+    pub min_version: Option<Version>,
+}
+
+/// The fields of the `[package]` table that are commonly needed together, e.g.
+/// for compatibility reports.
+///
+/// Fields that are absent are `None`. An inherited version is resolved through
+/// the workspace, while other inherited fields are `None`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PackageInfo {
+    /// The `package.name` field.
+    pub name: Option<String>,
+    /// The `package.version` field, see [`find_version`].
+    pub version: Option<Version>,
+    /// The `package.edition` field.
+    pub edition: Option<String>,
+    /// The `package.rust-version` field.
+    pub rust_version: Option<String>,
+}
+
+impl PackageInfo {
+    /// Renders the package information as a JSON object.
+    pub fn to_json(&self) -> String {
+        let version = self.version.as_ref().map(Version::to_string);
+        json::object([
+            ("name", json::opt_string(self.name.as_deref())),
+            ("version", json::opt_string(version.as_deref())),
+            ("edition", json::opt_string(self.edition.as_deref())),
+            (
+                "rust_version",
+                json::opt_string(self.rust_version.as_deref()),
+            ),
+        ])
+    }
+
+    /// Reads the fields besides the version, which needs the path of the file
+    /// to be resolved.
+    fn from_document(doc: &Document) -> Self {
+        let field = |key: &str| {
+            doc.get("package")
+                .and_then(|package| package.get(key))
+                .and_then(Item::as_str)
+                .map(str::to_string)
+        };
+        Self {
+            name: field("name"),
+            version: None,
+            edition: field("edition"),
+            rust_version: field("rust-version"),
+        }
+    }
+}
+
+/// The package information of two `Cargo.toml` files side by side, see
+/// [`compare_packages`].
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct PackageComparison {
+    pub left: PackageInfo,
+    pub right: PackageInfo,
+}
+
+impl PackageComparison {
+    /// Returns how the left version relates to the right one by semver
+    /// precedence, which ignores build metadata. Is `None` if a side has no
+    /// version.
+    pub fn ordering(&self) -> Option<Ordering> {
+        let left = self.left.version.as_ref()?;
+        let right = self.right.version.as_ref()?;
+        Some(left.cmp_precedence(right))
+    }
+
+    /// Returns the fields besides the version that differ, together with the
+    /// left and the right value. Absent fields are `None`.
+    pub fn differences(&self) -> Vec<(&'static str, Option<&str>, Option<&str>)> {
+        [
+            ("name", &self.left.name, &self.right.name),
+            ("edition", &self.left.edition, &self.right.edition),
+            (
+                "rust-version",
+                &self.left.rust_version,
+                &self.right.rust_version,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, left, right)| left != right)
+        .map(|(key, left, right)| (key, left.as_deref(), right.as_deref()))
+        .collect()
+    }
+
+    /// Renders the comparison as a JSON object.
+    pub fn to_json(&self) -> String {
+        let ordering = self.ordering().map(|ordering| match ordering {
+            Ordering::Less => "less",
+            Ordering::Equal => "equal",
+            Ordering::Greater => "greater",
+        });
+        let differences = self.differences().into_iter().map(|(key, left, right)| {
+            let sides = json::object([
+                ("left", json::opt_string(left)),
+                ("right", json::opt_string(right)),
+            ]);
+            (key, sides)
+        });
+        json::object([
+            ("left", self.left.to_json()),
+            ("right", self.right.to_json()),
+            ("ordering", json::opt_string(ordering)),
+            ("differences", json::object(differences)),
+        ])
+    }
+}
+
+/// The release information recorded in `[package.metadata.cargo-next]`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ReleaseHistory {
+    /// The recorded keys and values in the order they appear in the file.
+    /// Values that are not strings are rendered as toml.
+    pub entries: Vec<(String, String)>,
+}
+
+impl ReleaseHistory {
+    /// Returns whether nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Renders the history as a JSON object.
+    pub fn to_json(&self) -> String {
+        json::object(
+            self.entries
+                .iter()
+                .map(|(key, value)| (key.as_str(), json::string(value))),
+        )
+    }
+}
+
+/// Returns the path of the `Cargo.toml` file that a user-given path refers to.
+///
+/// A path to a `.toml` file is used as-is, any other path is taken as the
+/// directory that contains the `Cargo.toml` file.
+pub fn resolve_manifest_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match path.extension() {
+        Some(extension) if extension == "toml" => path.to_path_buf(),
+        _ => path.join("Cargo.toml"),
+    }
+}
+
+/// Returns the name, version, edition and rust-version of the package inside
+/// a `Cargo.toml` file.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+///
+/// # Returns
+///
+/// The package information or an error if the file could not be read or the
+/// version is malformed. Absent fields are not an error.
+pub fn get_package_info(path: impl AsRef<Path>) -> Result<PackageInfo, Error> {
     let cargo_toml_content = fs::read_to_string(path.as_ref())?;
     let doc = cargo_toml_content.parse::<Document>()?;
-    let item: &Item = &doc["package"]["version"];
+    Ok(PackageInfo {
+        version: find_version(path)?,
+        ..PackageInfo::from_document(&doc)
+    })
+}
+
+/// Checks that the package inside a `Cargo.toml` file has the expected name.
+///
+/// # Returns
+///
+/// [`Error::UnexpectedPackageName`] with the actual name if it differs.
+pub fn expect_package_name(path: impl AsRef<Path>, expected: &str) -> Result<(), Error> {
+    let actual = get_package_name(path)?;
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(Error::UnexpectedPackageName {
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
+
+/// Compares the versions and the package information of two `Cargo.toml`
+/// files, e.g. of a crate and its vendored copy.
+///
+/// # Arguments
+///
+/// - `left`: The path to the first `Cargo.toml` file.
+/// - `right`: The path to the second `Cargo.toml` file.
+///
+/// # Returns
+///
+/// The package information of both files or an error if one could not be
+/// read.
+pub fn compare_packages(
+    left: impl AsRef<Path>,
+    right: impl AsRef<Path>,
+) -> Result<PackageComparison, Error> {
+    Ok(PackageComparison {
+        left: get_package_info(left)?,
+        right: get_package_info(right)?,
+    })
+}
+
+/// Returns the name of the package inside a `Cargo.toml` file.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+///
+/// # Returns
+///
+/// The `package.name` field or an error if it is missing or not a string.
+pub fn get_package_name(path: impl AsRef<Path>) -> Result<String, Error> {
+    let cargo_toml_content = fs::read_to_string(path.as_ref())?;
+    let doc = cargo_toml_content.parse::<Document>()?;
+    let item: &Item = &doc["package"]["name"];
 
-    // This should be the case for valid Cargo.toml files.
     if let Some(s) = item.as_str() {
         Ok(s.to_string())
+    } else {
+        Err(Error::InvalidFieldType {
+            field: "name".to_string(),
+            ty: "string".to_string(),
+        })
+    }
+}
+
+/// Returns the version string inside a `Cargo.toml` file without parsing it.
+///
+/// With `version.workspace = true`, the version is read from
+/// `[workspace.package]` of the workspace root.
+pub fn get_package_version_str(path: impl AsRef<Path>) -> Result<String, Error> {
+    let source = VersionSource::load(path.as_ref())?;
+
+    // This should be the case for valid Cargo.toml files.
+    if let Some(s) = source.item().and_then(Item::as_str) {
+        Ok(s.to_string())
     } else {
         Err(Error::InvalidFieldType {
             field: "version".to_string(),
@@ -58,15 +445,127 @@ pub fn get_package_version_str(path: impl AsRef<Path>) -> Result<String, Error>
 /// # Returns
 ///
 /// The version as a `String` if it could be successfully extracted, otherwise
-/// an error.
+/// an error. A version that still contains a template placeholder like
+/// `${VERSION}` results in [`Error::TemplatePlaceholder`]. An inherited
+/// version is read from the workspace root, failing with
+/// [`Error::WorkspaceRootNotFound`] if there is none.
 pub fn get_version(path: impl AsRef<Path>) -> Result<Version, Error> {
     let cargo_toml_content = fs::read_to_string(path.as_ref())?;
+    match parse_manifest_version(&cargo_toml_content) {
+        Err(Error::InvalidFieldType { .. })
+            if inherits_version(&cargo_toml_content.parse::<Document>()?) =>
+        {
+            parse_version(&get_package_version_str(path)?)
+        }
+        result => result,
+    }
+}
+
+/// Returns the version inside a `Cargo.toml` file like [`get_version`], but
+/// distinguishes a missing version from a malformed one.
+///
+/// # Returns
+///
+/// The version, `None` if the file or its `package.version` field does not
+/// exist, or an error if the file or the version is malformed.
+pub fn find_version(path: impl AsRef<Path>) -> Result<Option<Version>, Error> {
+    let cargo_toml_content = match fs::read_to_string(path.as_ref()) {
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        content => content?,
+    };
+    let doc = cargo_toml_content.parse::<Document>()?;
+    if doc
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .is_none()
+    {
+        return Ok(None);
+    }
+    get_version(path).map(Some)
+}
+
+/// Returns whether a manifest declares `version.workspace = true`.
+fn inherits_version(doc: &Document) -> bool {
+    doc.get("package")
+        .and_then(|package| package.get("version"))
+        .and_then(|version| version.get("workspace"))
+        .and_then(Item::as_bool)
+        == Some(true)
+}
+
+/// Returns the `Cargo.toml` file that declares the version of a package, which
+/// is the workspace root for `version.workspace = true` and the manifest
+/// itself otherwise.
+pub fn version_manifest(path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    Ok(VersionSource::load(path.as_ref())?.path)
+}
+
+/// The manifest that declares the version of a package, which is the
+/// workspace root for `version.workspace = true`.
+struct VersionSource {
+    path: PathBuf,
+    content: String,
+    doc: Document,
+    inherited: bool,
+}
+
+impl VersionSource {
+    fn load(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)?;
+        let doc = content.parse::<Document>()?;
+        if !inherits_version(&doc) {
+            return Ok(Self {
+                path: path.to_path_buf(),
+                content,
+                doc,
+                inherited: false,
+            });
+        }
+        let root = workspace::find_workspace_root(path)?;
+        let content = fs::read_to_string(&root)?;
+        Ok(Self {
+            doc: content.parse::<Document>()?,
+            content,
+            path: root,
+            inherited: true,
+        })
+    }
+
+    /// Returns the table that contains the `version` field.
+    fn table(&self) -> Option<&Item> {
+        match self.inherited {
+            true => self.doc.get("workspace")?.get("package"),
+            false => self.doc.get("package"),
+        }
+    }
+
+    fn item(&self) -> Option<&Item> {
+        self.table()?.get("version")
+    }
+
+    fn item_mut(&mut self) -> &mut Item {
+        match self.inherited {
+            true => &mut self.doc["workspace"]["package"]["version"],
+            false => &mut self.doc["package"]["version"],
+        }
+    }
+}
+
+/// Returns the version inside the content of a `Cargo.toml` file, see
+/// [`get_version`].
+pub fn parse_manifest_version(cargo_toml_content: &str) -> Result<Version, Error> {
+    // Parsing huge manifests completely is slow, so try the line scanner first.
+    if let Some(s) = scan_package_version(cargo_toml_content) {
+        return parse_version(s);
+    }
     let doc = cargo_toml_content.parse::<Document>()?;
-    let item: &Item = &doc["package"]["version"];
+    let item = doc
+        .get("package")
+        .and_then(|package| package.get("version"));
 
     // This should be the case for valid Cargo.toml files.
-    if let Some(s) = item.as_str() {
-        Ok(Version::parse(s)?)
+    if let Some(s) = item.and_then(Item::as_str) {
+        parse_version(s)
     } else {
         Err(Error::InvalidFieldType {
             field: "version".to_string(),
@@ -75,8 +574,75 @@ pub fn get_version(path: impl AsRef<Path>) -> Result<Version, Error> {
     }
 }
 
+/// Returns the version inside a `Cargo.toml` file without its pre-release and
+/// build metadata, e.g. `1.2.0` for `1.2.0-rc.3+build`.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+///
+/// # Returns
+///
+/// The plain `x.y.z` version or an error if the version could not be read.
+pub fn core_version(path: impl AsRef<Path>) -> Result<Version, Error> {
+    let mut version = get_version(path)?;
+    version.pre = Prerelease::EMPTY;
+    version.build = BuildMetadata::EMPTY;
+    Ok(version)
+}
+
+/// The versions of a package before and after a change.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VersionChange {
+    /// The previous version, or `None` if the manifest had no valid one.
+    pub from: Option<Version>,
+    /// The new version.
+    pub to: Version,
+}
+
+impl VersionChange {
+    /// Returns whether the version stayed the same.
+    pub fn is_unchanged(&self) -> bool {
+        self.from.as_ref() == Some(&self.to)
+    }
+}
+
+impl fmt::Display for VersionChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.from {
+            _ if self.is_unchanged() => write!(f, "unchanged: {}", self.to),
+            Some(from) => write!(f, "{from} -> {}", self.to),
+            None => write!(f, "{}", self.to),
+        }
+    }
+}
+
+/// A `Cargo.toml` file whose version got replaced in memory.
+struct RenderedVersion {
+    /// The file that declares the version.
+    path: PathBuf,
+    change: VersionChange,
+    original: String,
+    content: String,
+}
+
+impl RenderedVersion {
+    /// Writes the file, unless that would not change its content.
+    fn write(self) -> Result<VersionChange, Error> {
+        if self.content != self.original {
+            write_manifest(&self.path, &self.content)?;
+        }
+        Ok(self.change)
+    }
+}
+
 /// Sets the version inside a `Cargo.toml` file.
 ///
+/// A package declared through dotted keys like `package.version = "1.0.0"`
+/// gets updated in place without adding a `[package]` header. With
+/// `version.workspace = true`, `[workspace.package]` of the workspace root
+/// gets updated instead of the member.
+///
 /// # Arguments
 ///
 /// - `path`: The path to the `Cargo.toml` file.
@@ -85,103 +651,2218 @@ pub fn get_version(path: impl AsRef<Path>) -> Result<Version, Error> {
 ///
 /// # Returns
 ///
-/// An error if something went wrong during IO operations or parsing.
-pub fn set_version(path: impl AsRef<Path>, version_str: impl AsRef<str>) -> Result<Version, Error> {
-    let version = Version::parse(version_str.as_ref())?;
-    let cargo_toml_content = fs::read_to_string(path.as_ref())?;
-    let mut doc = cargo_toml_content.parse::<Document>()?;
+/// The previous and the new version or an error if something went wrong
+/// during IO operations or parsing. Setting the version that is already
+/// present leaves the file untouched.
+pub fn set_version(
+    path: impl AsRef<Path>,
+    version_str: impl AsRef<str>,
+) -> Result<VersionChange, Error> {
+    set_version_with_options(path, version_str, &WriteOptions::default())
+}
 
-    doc["package"]["version"] = value(&version.to_string());
-    fs::write(path.as_ref(), doc.to_string())?;
+/// Sets the version inside a `Cargo.toml` file like [`set_version`], but
+/// writes the file according to the given options.
+pub fn set_version_with_options(
+    path: impl AsRef<Path>,
+    version_str: impl AsRef<str>,
+    options: &WriteOptions,
+) -> Result<VersionChange, Error> {
+    let version = parse_version(version_str.as_ref())?;
+    render_parsed_version(path.as_ref(), &version, options)?.write()
+}
 
-    Ok(version)
+/// Sets the version inside a `Cargo.toml` file like
+/// [`set_version_with_options`], but only in memory.
+///
+/// The file is left untouched. The returned content is exactly what would
+/// have been written to it, or to the workspace root for
+/// `version.workspace = true`.
+///
+/// # Returns
+///
+/// The new version together with the modified content of the file.
+pub fn render_version(
+    path: impl AsRef<Path>,
+    version_str: impl AsRef<str>,
+    options: &WriteOptions,
+) -> Result<(Version, String), Error> {
+    let version = parse_version(version_str.as_ref())?;
+    let rendered = render_parsed_version(path.as_ref(), &version, options)?;
+    Ok((version, rendered.content))
 }
 
-/// Bumps the version inside a `Cargo.toml` file according to semver specs.
+/// Sets an already parsed version inside a `Cargo.toml` file.
+///
+/// Unlike [`set_version`], the version does not get parsed again from a
+/// string, so it is written exactly as [`Version`] displays it.
 ///
 /// # Arguments
 ///
 /// - `path`: The path to the `Cargo.toml` file.
-/// - `type`: The type of bump. Either patch, minor or major.
+/// - `version`: The version to write into the file.
 ///
 /// # Returns
 ///
-/// The new version or an error if something went wrong during IO operations.
-pub fn bump_toml_version(path: impl AsRef<Path>, increment: Increment) -> Result<Version, Error> {
-    let version_str = get_package_version_str(path.as_ref())?;
-    let version = bump_version(&version_str, increment)?;
-    set_version(path, &version.to_string())?;
-    Ok(version)
+/// An error if something went wrong during IO operations or parsing.
+pub fn set_version_parsed(path: impl AsRef<Path>, version: &Version) -> Result<(), Error> {
+    render_parsed_version(path.as_ref(), version, &WriteOptions::default())?.write()?;
+    Ok(())
 }
 
-pub fn bump_version(version_str: &str, increment: Increment) -> Result<Version, Error> {
-    let mut version: Version = Version::parse(version_str)?;
-    match increment {
-        Increment::Major => version.bump_major(),
-        Increment::Minor => version.bump_minor(),
-        Increment::Patch => version.bump_patch(),
+/// Replaces the version in the `Cargo.toml` file that declares it, but only in
+/// memory.
+fn render_parsed_version(
+    path: &Path,
+    version: &Version,
+    options: &WriteOptions,
+) -> Result<RenderedVersion, Error> {
+    let mut source = VersionSource::load(path)?;
+    if source.inherited && source.item().is_none() {
+        return Err(Error::InvalidFieldType {
+            field: "workspace.package.version".to_string(),
+            ty: "string".to_string(),
+        });
     }
-    Ok(version)
-}
 
-trait SemVerExt {
-    fn increment_major(&mut self);
-    fn increment_minor(&mut self);
-    fn increment_patch(&mut self);
+    if !options.allow_zero && *version == Version::new(0, 0, 0) {
+        return Err(Error::ZeroVersion);
+    }
+    if let Some(min) = options.min_version.as_ref() {
+        if version.cmp_precedence(min).is_lt() {
+            return Err(Error::BelowMinimum {
+                min: min.clone(),
+                version: version.clone(),
+            });
+        }
+    }
+    if options.forbid_downgrade {
+        let current = source
+            .item()
+            .and_then(Item::as_str)
+            .map(parse_version)
+            .transpose()?;
+        if let Some(current) = current {
+            check_downgrade(&current, version, options.allow_prerelease_downgrade)?;
+        }
+    }
+    let from = source
+        .item()
+        .and_then(Item::as_str)
+        .and_then(|s| Version::parse(s).ok());
+    set_version_item(source.item_mut(), &version.to_string());
 
-    fn bump_major(&mut self);
-    fn bump_minor(&mut self);
-    fn bump_patch(&mut self);
+    Ok(RenderedVersion {
+        content: render_manifest(&source.doc, options),
+        path: source.path,
+        change: VersionChange {
+            from,
+            to: version.clone(),
+        },
+        original: source.content,
+    })
 }
 
-impl SemVerExt for Version {
-    fn increment_major(&mut self) {
-        self.major += 1;
-    }
+/// Returns whether going from `old` to `new` reopens a pre-release of the same
+/// release, e.g. `1.0.0` to `1.0.0-rc.4`.
+///
+/// That is the case if both share `x.y.z` and only `new` has a pre-release.
+pub fn is_reopened_prerelease(old: &Version, new: &Version) -> bool {
+    (old.major, old.minor, old.patch) == (new.major, new.minor, new.patch)
+        && old.pre.is_empty()
+        && !new.pre.is_empty()
+}
 
-    fn increment_minor(&mut self) {
-        self.minor += 1;
+/// Checks that going from `old` to `new` is no downgrade by semver precedence,
+/// which ignores build metadata.
+///
+/// # Arguments
+///
+/// - `old`: The current version.
+/// - `new`: The version to write.
+/// - `allow_reopen`: Whether reopening a pre-release is allowed, see
+///   [`is_reopened_prerelease`].
+///
+/// # Returns
+///
+/// [`Error::Downgrade`] if `new` is lower than `old`.
+pub fn check_downgrade(old: &Version, new: &Version, allow_reopen: bool) -> Result<(), Error> {
+    if new.cmp_precedence(old).is_ge() || (allow_reopen && is_reopened_prerelease(old, new)) {
+        Ok(())
+    } else {
+        Err(Error::Downgrade {
+            from: old.clone(),
+            to: new.clone(),
+        })
     }
+}
 
-    fn increment_patch(&mut self) {
-        self.patch += 1;
-    }
+/// The result of writing the unchanged version back into a `Cargo.toml` file
+/// in memory, see [`round_trip`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RoundTrip {
+    /// The file that declares the version, which is the workspace root for
+    /// `version.workspace = true`.
+    pub path: PathBuf,
+    /// The original content of the file.
+    pub original: String,
+    /// The content that writing the same version would produce.
+    pub rewritten: String,
+}
 
-    fn bump_major(&mut self) {
-        self.major += 1;
-        self.minor = 0;
-        self.patch = 0;
+impl RoundTrip {
+    /// Returns whether the round-trip preserved the file byte for byte.
+    pub fn is_identical(&self) -> bool {
+        self.original == self.rewritten
     }
 
-    fn bump_minor(&mut self) {
-        self.minor += 1;
-        self.patch = 0;
+    /// Writes the rewritten content to the file.
+    pub fn write(&self) -> Result<(), Error> {
+        write_manifest(&self.path, &self.rewritten)
     }
 
-    fn bump_patch(&mut self) {
-        self.patch += 1;
+    /// Returns the lines that differ as their 1-based line number together
+    /// with the original and rewritten line. A line is `None` if one side has
+    /// fewer lines.
+    pub fn changed_lines(&self) -> Vec<(usize, Option<&str>, Option<&str>)> {
+        let mut original = self.original.split_inclusive('\n');
+        let mut rewritten = self.rewritten.split_inclusive('\n');
+        let mut changed = Vec::new();
+        for line in 1.. {
+            match (original.next(), rewritten.next()) {
+                (None, None) => break,
+                (a, b) if a != b => changed.push((line, a, b)),
+                _ => {}
+            }
+        }
+        changed
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::{bump_version, Increment};
+/// Writes the version of a `Cargo.toml` file back into it without changing
+/// it, but only in memory.
+///
+/// This shows whether writing a version preserves the formatting of that
+/// specific file. Nothing gets written to disk.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+///
+/// # Returns
+///
+/// The original and the rewritten content or an error if the file could not
+/// be read or has no string version.
+pub fn round_trip(path: impl AsRef<Path>) -> Result<RoundTrip, Error> {
+    let mut source = VersionSource::load(path.as_ref())?;
+    let version = source
+        .item()
+        .and_then(Item::as_str)
+        .ok_or_else(|| Error::InvalidFieldType {
+            field: "version".to_string(),
+            ty: "string".to_string(),
+        })?
+        .to_string();
+    set_version_item(source.item_mut(), &version);
 
-    #[test]
-    fn test_version_bump() {
-        const BASE_VERSION: &str = "0.1.0";
-        let mut v = bump_version(BASE_VERSION, Increment::Patch).unwrap();
-        assert_eq!(&v.to_string(), "0.1.1");
-        v = bump_version(&v.to_string(), Increment::Minor).unwrap();
-        assert_eq!(&v.to_string(), "0.2.0");
-        v = bump_version(&v.to_string(), Increment::Patch).unwrap();
-        v = bump_version(&v.to_string(), Increment::Patch).unwrap();
-        assert_eq!(&v.to_string(), "0.2.2");
-        v = bump_version(&v.to_string(), Increment::Major).unwrap();
-        assert_eq!(&v.to_string(), "1.0.0");
-        v = bump_version(&v.to_string(), Increment::Minor).unwrap();
-        assert_eq!(&v.to_string(), "1.1.0");
-        v = bump_version(&v.to_string(), Increment::Patch).unwrap();
-        assert_eq!(&v.to_string(), "1.1.1");
+    Ok(RoundTrip {
+        rewritten: source.doc.to_string(),
+        original: source.content,
+        path: source.path,
+    })
+}
+
+/// Bumps the version inside a `Cargo.toml` file according to semver specs.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `type`: The type of bump. Either patch, minor or major.
+///
+/// # Returns
+///
+/// The previous and the new version or an error if something went wrong
+/// during IO operations.
+pub fn bump_toml_version(
+    path: impl AsRef<Path>,
+    increment: Increment,
+) -> Result<VersionChange, Error> {
+    bump_toml_version_with_options(path, increment, &WriteOptions::default())
+}
+
+/// Bumps the version inside a `Cargo.toml` file like [`bump_toml_version`],
+/// but writes the file according to the given options.
+pub fn bump_toml_version_with_options(
+    path: impl AsRef<Path>,
+    increment: Increment,
+    options: &WriteOptions,
+) -> Result<VersionChange, Error> {
+    let version = compute_bumped_version(path.as_ref(), increment)?;
+    set_version_with_options(path, version.to_string(), options)
+}
+
+/// Returns the version that [`bump_toml_version`] would write, without
+/// writing it.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `increment`: The increment to apply to the current version.
+///
+/// # Returns
+///
+/// The bumped version or an error if the current version could not be read.
+pub fn compute_bumped_version(
+    path: impl AsRef<Path>,
+    increment: Increment,
+) -> Result<Version, Error> {
+    let version_str = get_package_version_str(path)?;
+    bump_version(&version_str, increment)
+}
+
+/// Bumps the version inside a `Cargo.toml` file and opens a new pre-release
+/// train on it.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `increment`: The field to bump before starting the pre-release.
+/// - `label`: The pre-release label, e.g. `rc`.
+/// - `options`: How the file gets written, see [`WriteOptions`].
+///
+/// # Returns
+///
+/// The previous and the new version or an error if something went wrong
+/// during IO operations or the options refused the version.
+pub fn start_toml_prerelease(
+    path: impl AsRef<Path>,
+    increment: Increment,
+    label: &str,
+    options: &WriteOptions,
+) -> Result<VersionChange, Error> {
+    let version_str = get_package_version_str(path.as_ref())?;
+    let version = start_prerelease(&version_str, increment, label)?;
+    set_version_with_options(path, version.to_string(), options)
+}
+
+/// Advances the pre-release counter of the version inside a `Cargo.toml` file.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `options`: How the file gets written, see [`WriteOptions`].
+///
+/// # Returns
+///
+/// The previous and the new version or an error if something went wrong
+/// during IO operations or the options refused the version.
+pub fn advance_toml_prerelease(
+    path: impl AsRef<Path>,
+    options: &WriteOptions,
+) -> Result<VersionChange, Error> {
+    let version_str = get_package_version_str(path.as_ref())?;
+    let version = advance_prerelease(&version_str)?;
+    set_version_with_options(path, version.to_string(), options)
+}
+
+/// Starts a pre-release on the version inside a `Cargo.toml` file without
+/// bumping any field.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `label`: The pre-release label, e.g. `rc`.
+/// - `options`: How the file gets written, see [`WriteOptions`].
+///
+/// # Returns
+///
+/// The previous and the new version or an error if something went wrong
+/// during IO operations or the options refused the version.
+pub fn open_toml_prerelease(
+    path: impl AsRef<Path>,
+    label: &str,
+    options: &WriteOptions,
+) -> Result<VersionChange, Error> {
+    let version_str = get_package_version_str(path.as_ref())?;
+    let version = open_prerelease(&version_str, label)?;
+    set_version_with_options(path, version.to_string(), options)
+}
+
+/// Removes the pre-release from the version inside a `Cargo.toml` file.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `options`: How the file gets written, see [`WriteOptions`].
+///
+/// # Returns
+///
+/// The previous and the new version or an error if something went wrong
+/// during IO operations or the options refused the version.
+pub fn finalize_toml_prerelease(
+    path: impl AsRef<Path>,
+    options: &WriteOptions,
+) -> Result<VersionChange, Error> {
+    let version_str = get_package_version_str(path.as_ref())?;
+    let version = finalize_prerelease(&version_str)?;
+    set_version_with_options(path, version.to_string(), options)
+}
+
+/// Attaches build metadata to the version inside a `Cargo.toml` file, see
+/// [`with_build_metadata`].
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `meta`: The build metadata, e.g. a git short hash like `abc1234`.
+/// - `options`: How the file gets written, see [`WriteOptions`].
+///
+/// # Returns
+///
+/// The previous and the new version or [`Error::SemverParseError`] if the
+/// metadata is invalid.
+pub fn set_build_metadata(
+    path: impl AsRef<Path>,
+    meta: &str,
+    options: &WriteOptions,
+) -> Result<VersionChange, Error> {
+    let version_str = get_package_version_str(path.as_ref())?;
+    let version = with_build_metadata(&version_str, meta)?;
+    set_version_with_options(path, version.to_string(), options)
+}
+
+/// Switches the label of the pre-release of the version inside a `Cargo.toml`
+/// file, see [`relabel_prerelease`].
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `from`: The label the pre-release has to have, e.g. `alpha`.
+/// - `to`: The new label, e.g. `beta`.
+/// - `options`: How the file gets written, see [`WriteOptions`].
+///
+/// # Returns
+///
+/// The previous and the new version, or `None` if the label did not match and
+/// nothing has been written.
+pub fn relabel_toml_prerelease(
+    path: impl AsRef<Path>,
+    from: &str,
+    to: &str,
+    options: &WriteOptions,
+) -> Result<Option<VersionChange>, Error> {
+    let version_str = get_package_version_str(path.as_ref())?;
+    let Some(version) = relabel_prerelease(&version_str, from, to)? else {
+        return Ok(None);
+    };
+    set_version_with_options(path, version.to_string(), options).map(Some)
+}
+
+/// Applies an increment to a version.
+///
+/// Every increment drops the build metadata, which describes the previous
+/// version. Major, minor and patch increments drop the pre-release as well,
+/// e.g. a patch increment turns `1.2.3-rc.1+sha.5` into `1.2.4`, while a
+/// pre-release increment turns `1.2.3-rc.1+sha.5` into `1.2.3-rc.2`.
+///
+/// Note that this changes the behavior of earlier releases, where increments
+/// kept both the pre-release and the build metadata.
+pub fn bump_version(version_str: &str, increment: Increment) -> Result<Version, Error> {
+    let mut version: Version = parse_version(version_str)?;
+    match increment {
+        Increment::Major => version.bump_major(),
+        Increment::Minor => version.bump_minor(),
+        Increment::Patch => version.bump_patch(),
+        Increment::Pre(label) => version = bump_prerelease(version_str, &label)?,
+        Increment::Finalize => version = finalize_prerelease(version_str)?,
+    }
+    version.build = BuildMetadata::EMPTY;
+    Ok(version)
+}
+
+/// Applies several increments to a version in order, each one building on the
+/// result of the previous one.
+///
+/// For example, a minor followed by a patch increment turns `1.2.5` into
+/// `1.3.1`. Note that the order matters and that repeated increments are all
+/// applied, so two patch increments turn `1.2.5` into `1.2.7`.
+pub fn bump_version_sequence(
+    version_str: &str,
+    increments: &[Increment],
+) -> Result<Version, Error> {
+    let version = parse_version(version_str)?;
+    increments.iter().try_fold(version, |version, increment| {
+        bump_version(&version.to_string(), increment.clone())
+    })
+}
+
+/// Looks up `package.version` by scanning the lines of a manifest instead of
+/// parsing it completely.
+///
+/// Only the common layout of a `version = "..."` line inside a `[package]`
+/// table is recognized. `None` is returned for anything that could be
+/// ambiguous without a real parser, e.g. multi-line strings, escapes, dotted
+/// keys or inline tables, in which case the caller must fall back to a full
+/// parse.
+fn scan_package_version(content: &str) -> Option<&str> {
+    let mut in_package = false;
+    let mut seen_header = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.contains("\"\"\"") || line.contains("'''") {
+            return None;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.split('#').next()?.trim_end();
+            if header.contains(['"', '\'']) {
+                return None;
+            }
+            if in_package {
+                // The `[package]` table ended without a plain version key.
+                return None;
+            }
+            seen_header = true;
+            in_package = header.strip_suffix(']')?.trim() == "package";
+            continue;
+        }
+
+        if !seen_header && line.starts_with("package") {
+            // `package.version = ...` or `package = { ... }` at the root.
+            return None;
+        }
+        if !in_package {
+            continue;
+        }
+
+        let Some(rest) = line.strip_prefix("version") else {
+            continue;
+        };
+        let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, tail) = rest[1..].split_once(quote)?;
+        let tail = tail.trim_start();
+        if (quote == '"' && value.contains('\\')) || !(tail.is_empty() || tail.starts_with('#')) {
+            return None;
+        }
+        return Some(value);
+    }
+    None
+}
+
+/// Parses a semver version, reporting unexpanded template placeholders like
+/// `${VERSION}` or `{{ version }}` with a dedicated error.
+fn parse_version(s: &str) -> Result<Version, Error> {
+    if s.contains("${") || s.contains("{{") {
+        return Err(Error::TemplatePlaceholder {
+            value: s.to_string(),
+        });
+    }
+    Ok(Version::parse(s)?)
+}
+
+/// Returns whether a string is a valid semver version.
+///
+/// Partial versions like `1.2` and prefixed versions like `v1.2.3` are not
+/// valid.
+pub fn is_valid_version(s: &str) -> bool {
+    Version::parse(s).is_ok()
+}
+
+/// Parses a string as a semver version.
+///
+/// # Returns
+///
+/// The parsed version or a [`Error::SemverParseError`] describing why the
+/// string is not a valid semver version.
+pub fn validate_version_string(s: &str) -> Result<Version, Error> {
+    parse_version(s)
+}
+
+/// Parses a version like [`validate_version_string`], but ignores leading
+/// zeros in the `x.y.z` fields, so that `1.02.3` parses as `1.2.3`.
+pub fn parse_lenient_version(s: &str) -> Result<Version, Error> {
+    let (core, suffix) = s.split_at(s.find(['-', '+']).unwrap_or(s.len()));
+    let core: Vec<_> = core
+        .split('.')
+        .map(|field| match field.trim_start_matches('0') {
+            "" if !field.is_empty() => "0",
+            trimmed => trimmed,
+        })
+        .collect();
+    parse_version(&format!("{}{suffix}", core.join(".")))
+}
+
+/// Bumps the given field of a version and starts a pre-release on the result,
+/// e.g. `1.2.3` with a minor increment and label `rc` becomes `1.3.0-rc.1`.
+pub fn start_prerelease(
+    version_str: &str,
+    increment: Increment,
+    label: &str,
+) -> Result<Version, Error> {
+    let version = bump_version(version_str, increment)?;
+    open_prerelease(&version.to_string(), label)
+}
+
+/// Starts a pre-release on a version without bumping any field, e.g. `1.2.3`
+/// with label `rc` becomes `1.2.3-rc.1`. An existing pre-release gets
+/// replaced. Unlike with [`bump_version`], build metadata is kept.
+pub fn open_prerelease(version_str: &str, label: &str) -> Result<Version, Error> {
+    let mut version = parse_version(version_str)?;
+    version.pre = Prerelease::new(&format!("{label}.1"))?;
+    Ok(version)
+}
+
+/// Removes the pre-release from a version, e.g. `1.2.0-beta.3` becomes
+/// `1.2.0`. Unlike with [`bump_version`], build metadata is kept.
+pub fn finalize_prerelease(version_str: &str) -> Result<Version, Error> {
+    let mut version = parse_version(version_str)?;
+    version.pre = Prerelease::EMPTY;
+    Ok(version)
+}
+
+/// Replaces the build metadata of a version while keeping all other fields,
+/// e.g. `1.4.2-rc.1+old` becomes `1.4.2-rc.1+abc1234`. Empty metadata removes
+/// it.
+///
+/// The metadata must consist of dot-separated identifiers made of ASCII
+/// alphanumerics and hyphens, otherwise [`Error::SemverParseError`] is
+/// returned.
+pub fn with_build_metadata(version_str: &str, meta: &str) -> Result<Version, Error> {
+    let mut version = parse_version(version_str)?;
+    version.build = BuildMetadata::new(meta)?;
+    Ok(version)
+}
+
+/// Switches the label of a pre-release and resets its counter, e.g.
+/// `1.3.0-alpha.4` becomes `1.3.0-beta.1` when promoting `alpha` to `beta`.
+///
+/// The label is everything in front of the trailing numeric counter, so
+/// `alpha` matches both `1.3.0-alpha` and `1.3.0-alpha.4`.
+///
+/// # Returns
+///
+/// The relabeled version, or `None` if the version has no pre-release with
+/// the label `from`.
+pub fn relabel_prerelease(
+    version_str: &str,
+    from: &str,
+    to: &str,
+) -> Result<Option<Version>, Error> {
+    let version = parse_version(version_str)?;
+    if version.pre.is_empty() || prerelease_label(&version.pre) != from {
+        return Ok(None);
+    }
+    open_prerelease(version_str, to).map(Some)
+}
+
+/// Returns the label of a pre-release, which is everything in front of the
+/// trailing numeric counter, e.g. `beta` for `beta.3`.
+fn prerelease_label(pre: &Prerelease) -> &str {
+    match pre.as_str().rsplit_once('.') {
+        Some((label, counter)) if counter.parse::<u64>().is_ok() => label,
+        _ => pre.as_str(),
+    }
+}
+
+/// Bumps the pre-release with the given label, e.g. `1.2.0` becomes
+/// `1.2.0-beta.1` and `1.2.0-beta.1` becomes `1.2.0-beta.2` for `beta`.
+///
+/// A pre-release with the label but without a counter gets one, so
+/// `1.2.0-beta` becomes `1.2.0-beta.1`.
+///
+/// # Returns
+///
+/// The bumped version or [`Error::PrereleaseLabelMismatch`] if the version
+/// has a pre-release with another label.
+pub fn bump_prerelease(version_str: &str, label: &str) -> Result<Version, Error> {
+    let version = parse_version(version_str)?;
+    if version.pre.is_empty() || version.pre.as_str() == label {
+        return open_prerelease(version_str, label);
+    }
+    if prerelease_label(&version.pre) != label {
+        return Err(Error::PrereleaseLabelMismatch {
+            expected: label.to_string(),
+            actual: version.pre.to_string(),
+        });
+    }
+    advance_prerelease(version_str)
+}
+
+/// Advances the trailing numeric counter of a pre-release, e.g. `1.3.0-rc.1`
+/// becomes `1.3.0-rc.2`. Unlike with [`bump_version`], build metadata is kept.
+///
+/// Zero-padded counters like `rc.01` are not supported. An identifier made of
+/// digits only is numeric in semver and must not have leading zeros, so such
+/// versions are rejected with [`Error::SemverParseError`] before any counter
+/// could be formatted.
+pub fn advance_prerelease(version_str: &str) -> Result<Version, Error> {
+    let mut version = parse_version(version_str)?;
+    let counter_err = || Error::NoPrereleaseCounter {
+        version: version_str.to_string(),
+    };
+
+    let (label, counter) = match version.pre.as_str().rsplit_once('.') {
+        Some((label, counter)) => (label, counter),
+        None => ("", version.pre.as_str()),
+    };
+    let counter: u64 = counter.parse().map_err(|_| counter_err())?;
+    let next = match label {
+        "" => (counter + 1).to_string(),
+        label => format!("{label}.{}", counter + 1),
+    };
+    version.pre = Prerelease::new(&next)?;
+    Ok(version)
+}
+
+/// Lets the user review and adjust a proposed version in an editor.
+///
+/// The version is written into a temporary file that gets opened in the
+/// editor. Once the editor exits, the first line that is neither empty nor a
+/// `#` comment is the adjusted version.
+///
+/// # Arguments
+///
+/// - `editor`: The editor command, usually the value of `$EDITOR`. It may
+///   contain arguments separated by whitespace, e.g. `code --wait`.
+/// - `proposed`: The version to start editing from.
+///
+/// # Returns
+///
+/// The adjusted version, which is not validated yet, or an error if the
+/// editor could not be run or exited unsuccessfully.
+pub fn edit_version(editor: &str, proposed: &str) -> Result<String, Error> {
+    let mut args = editor.split_whitespace();
+    let program = args.next().ok_or(Error::EditorNotSet)?;
+    // A fresh file avoids clobbering or following whatever sits at a
+    // predictable path in the shared temporary directory.
+    let mut file = tempfile::Builder::new()
+        .prefix("cargo-next-")
+        .suffix(".version")
+        .tempfile()?;
+    write!(
+        file,
+        "{proposed}\n# Adjust the version above, lines starting with # are ignored.\n"
+    )?;
+
+    let status = Command::new(program).args(args).arg(file.path()).status()?;
+    let content = fs::read_to_string(file.path());
+    file.close()?;
+    if !status.success() {
+        return Err(Error::EditorFailed { status });
+    }
+
+    Ok(content?
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Refreshes the `Cargo.lock` entry of the package inside a `Cargo.toml` file
+/// by running `cargo update --package <name>`.
+///
+/// The `cargo` binary is taken from the `CARGO` environment variable that
+/// cargo sets for its subcommands, falling back to `cargo` on the `PATH`.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+///
+/// # Returns
+///
+/// An error if `cargo` could not be run or exited unsuccessfully.
+pub fn cargo_update(path: impl AsRef<Path>) -> Result<(), Error> {
+    let name = get_package_name(path.as_ref())?;
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = Command::new(cargo)
+        .arg("update")
+        .arg("--manifest-path")
+        .arg(path.as_ref())
+        .arg("--package")
+        .arg(&name)
+        .status()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Error::CargoNotFound,
+            _ => Error::IoError(e),
+        })?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::CargoFailed { status })
+    }
+}
+
+/// Records the release date inside the `[package.metadata.cargo-next]` table of
+/// a `Cargo.toml` file. An already recorded date gets overwritten.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `clock`: The clock to read the current date from.
+/// - `format`: The format of the date, e.g. `%Y-%m-%d`. See
+///   [`DateTime::format`](clock::DateTime::format) for the supported
+///   specifiers.
+///
+/// # Returns
+///
+/// The recorded date or an error if something went wrong during IO operations.
+pub fn stamp_release_date(
+    path: impl AsRef<Path>,
+    clock: &impl Clock,
+    format: &str,
+) -> Result<String, Error> {
+    let cargo_toml_content = fs::read_to_string(path.as_ref())?;
+    let mut doc = cargo_toml_content.parse::<Document>()?;
+
+    let date = clock.now().format(format);
+    metadata_table_mut(&mut doc)?.insert("released", value(&date));
+    write_manifest(path.as_ref(), &doc.to_string())?;
+
+    Ok(date)
+}
+
+/// The format of the build metadata written by [`stamp_build_timestamp`], e.g.
+/// `20240501T120000Z`. Build metadata must not contain `:`, so the compact
+/// form of ISO 8601 is used.
+pub const BUILD_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Replaces the build metadata of the version inside a `Cargo.toml` file with
+/// the current UTC time, e.g. `1.2.3+20240501T120000Z`.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `clock`: The clock to read the current time from.
+///
+/// # Returns
+///
+/// The new version or an error if something went wrong during IO operations.
+pub fn stamp_build_timestamp(path: impl AsRef<Path>, clock: &impl Clock) -> Result<Version, Error> {
+    let mut version = get_version(path.as_ref())?;
+    version.build = BuildMetadata::new(&clock.now().format(BUILD_TIMESTAMP_FORMAT))?;
+    set_version_parsed(path, &version)?;
+    Ok(version)
+}
+
+/// Returns the release information recorded in the
+/// `[package.metadata.cargo-next]` table of a `Cargo.toml` file, e.g. the
+/// date written by [`stamp_release_date`].
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+///
+/// # Returns
+///
+/// The recorded history, which is empty if nothing has been recorded.
+pub fn get_release_history(path: impl AsRef<Path>) -> Result<ReleaseHistory, Error> {
+    let cargo_toml_content = fs::read_to_string(path.as_ref())?;
+    let doc = cargo_toml_content.parse::<Document>()?;
+
+    let table = doc
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get(METADATA_TABLE))
+        .and_then(Item::as_table_like);
+    let Some(table) = table else {
+        return Ok(ReleaseHistory::default());
+    };
+
+    let entries = table
+        .iter()
+        .map(|(key, item)| {
+            let value = match item.as_str() {
+                Some(s) => s.to_string(),
+                None => item.to_string().trim().to_string(),
+            };
+            (key.to_string(), value)
+        })
+        .collect();
+    Ok(ReleaseHistory { entries })
+}
+
+/// Returns the `[package.metadata.cargo-next]` table of a document, creating
+/// it and its parents if they do not exist yet.
+fn metadata_table_mut(doc: &mut Document) -> Result<&mut Table, Error> {
+    let mut table = doc.as_table_mut();
+    let mut field = String::new();
+    for key in ["package", "metadata", METADATA_TABLE] {
+        if !field.is_empty() {
+            field.push('.');
+        }
+        field.push_str(key);
+        table = table
+            .entry(key)
+            .or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            })
+            .as_table_mut()
+            .ok_or_else(|| Error::InvalidFieldType {
+                field: field.clone(),
+                ty: "table".to_string(),
+            })?;
+    }
+    Ok(table)
+}
+
+/// The change of a numeric version field, see [`VersionDelta`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FieldDelta {
+    pub old: u64,
+    pub new: u64,
+}
+
+impl FieldDelta {
+    /// Returns the signed difference between the new and the old value.
+    pub fn change(&self) -> i128 {
+        i128::from(self.new) - i128::from(self.old)
+    }
+}
+
+/// The change of the pre-release or the build metadata of a version, see
+/// [`VersionDelta`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum LabelDelta {
+    Unchanged,
+    Added(String),
+    Removed(String),
+    Changed { from: String, to: String },
+}
+
+impl LabelDelta {
+    fn new(old: &str, new: &str) -> Self {
+        match (old, new) {
+            (old, new) if old == new => Self::Unchanged,
+            ("", new) => Self::Added(new.to_string()),
+            (old, "") => Self::Removed(old.to_string()),
+            (old, new) => Self::Changed {
+                from: old.to_string(),
+                to: new.to_string(),
+            },
+        }
+    }
+
+    /// Returns the old and the new label. Both are `None` if the label did
+    /// not change.
+    pub fn sides(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            Self::Unchanged => (None, None),
+            Self::Added(new) => (None, Some(new)),
+            Self::Removed(old) => (Some(old), None),
+            Self::Changed { from, to } => (Some(from), Some(to)),
+        }
+    }
+}
+
+/// The field by field difference between two versions, see [`version_delta`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct VersionDelta {
+    pub major: FieldDelta,
+    pub minor: FieldDelta,
+    pub patch: FieldDelta,
+    pub pre: LabelDelta,
+    pub build: LabelDelta,
+}
+
+impl VersionDelta {
+    /// Returns the numeric fields from the most to the least significant one.
+    fn fields(&self) -> [(&'static str, FieldDelta); 3] {
+        [
+            ("major", self.major),
+            ("minor", self.minor),
+            ("patch", self.patch),
+        ]
+    }
+
+    /// Returns whether a field went back to 0 because a more significant
+    /// field was increased, e.g. the minor version when bumping the major.
+    fn is_reset(&self, index: usize) -> bool {
+        let (_, field) = self.fields()[index];
+        field.new == 0
+            && field.old != 0
+            && self.fields()[..index].iter().any(|(_, f)| f.change() > 0)
+    }
+
+    /// Renders the delta as a JSON object.
+    pub fn to_json(&self) -> String {
+        let fields = self.fields();
+        let numeric = fields.iter().enumerate().map(|(i, (name, field))| {
+            let value = json::object([
+                ("old", field.old.to_string()),
+                ("new", field.new.to_string()),
+                ("change", field.change().to_string()),
+                ("reset", self.is_reset(i).to_string()),
+            ]);
+            (*name, value)
+        });
+        let labels = [("pre", &self.pre), ("build", &self.build)].map(|(name, label)| {
+            let (old, new) = label.sides();
+            let value = json::object([
+                ("old", json::opt_string(old)),
+                ("new", json::opt_string(new)),
+            ]);
+            (name, value)
+        });
+        json::object(numeric.chain(labels))
+    }
+}
+
+impl fmt::Display for VersionDelta {
+    /// Formats the delta like `major +1, minor reset, patch reset`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        for (i, (name, field)) in self.fields().iter().enumerate() {
+            parts.push(match field.change() {
+                _ if self.is_reset(i) => format!("{name} reset"),
+                0 => format!("{name} unchanged"),
+                change if change > 0 => format!("{name} +{change}"),
+                change => format!("{name} {change}"),
+            });
+        }
+        for (name, label) in [("pre-release", &self.pre), ("build", &self.build)] {
+            match label {
+                LabelDelta::Unchanged => {}
+                LabelDelta::Added(new) => parts.push(format!("{name} {new} added")),
+                LabelDelta::Removed(old) => parts.push(format!("{name} {old} removed")),
+                LabelDelta::Changed { from, to } => parts.push(format!("{name} {from} -> {to}")),
+            }
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Computes how every field changes between two versions.
+///
+/// The delta also works for downgrades, whose changes are negative.
+pub fn version_delta(old: &Version, new: &Version) -> VersionDelta {
+    let field = |old, new| FieldDelta { old, new };
+    VersionDelta {
+        major: field(old.major, new.major),
+        minor: field(old.minor, new.minor),
+        patch: field(old.patch, new.patch),
+        pre: LabelDelta::new(old.pre.as_str(), new.pre.as_str()),
+        build: LabelDelta::new(old.build.as_str(), new.build.as_str()),
+    }
+}
+
+/// Formats a version as a Docker image tag, e.g. `myrepo/app:1.2.3`.
+///
+/// Build metadata is not allowed inside Docker tags. The `+` that separates it
+/// is replaced with `_`, or the build metadata is dropped if `strip_build` is
+/// set.
+pub fn docker_tag(image: &str, version: &Version, strip_build: bool) -> String {
+    let tag = if strip_build {
+        let mut version = version.clone();
+        version.build = BuildMetadata::EMPTY;
+        version.to_string()
+    } else {
+        version.to_string().replace('+', "_")
+    };
+    format!("{image}:{tag}")
+}
+
+/// Returns whether releasing a version breaks consumers that depend on the
+/// crate with the given requirement, i.e. whether the requirement does not
+/// match the version.
+///
+/// Like in cargo, pre-releases only match requirements that mention a
+/// pre-release of the same `x.y.z`, so `1.3.0-rc.1` breaks `^1.2`.
+///
+/// # Arguments
+///
+/// - `req`: The requirement of the consumer, e.g. `^1.2`.
+/// - `version`: The version that is planned to be released.
+///
+/// # Returns
+///
+/// Whether the version breaks the requirement or an error if the requirement
+/// could not be parsed.
+pub fn is_breaking_for(req: &str, version: &Version) -> Result<bool, Error> {
+    Ok(!VersionReq::parse(req)?.matches(version))
+}
+
+/// Renders the fields of a version as a JSON object, e.g.
+/// `{"major":1,"minor":2,"patch":3,"pre":"rc.1","build":null}`.
+///
+/// Absent pre-releases and build metadata are `null`.
+pub fn version_to_json(version: &Version) -> String {
+    let optional = |s: &str| json::opt_string(Some(s).filter(|s| !s.is_empty()));
+    json::object([
+        ("major", version.major.to_string()),
+        ("minor", version.minor.to_string()),
+        ("patch", version.patch.to_string()),
+        ("pre", optional(version.pre.as_str())),
+        ("build", optional(version.build.as_str())),
+    ])
+}
+
+/// Joins the `x.y.z` fields of a version with another separator, e.g. `1_2_3`
+/// for `1.2.3-rc.1` and `_`.
+///
+/// # Arguments
+///
+/// - `version`: The version to join.
+/// - `separator`: The separator between the fields.
+/// - `keep_suffix`: Whether the pre-release and build metadata get appended
+///   unchanged, e.g. `1_2_3-rc.1+sha.5`. They are omitted otherwise.
+pub fn join_version(version: &Version, separator: &str, keep_suffix: bool) -> String {
+    let mut joined = [version.major, version.minor, version.patch]
+        .map(|f| f.to_string())
+        .join(separator);
+    if keep_suffix && !version.pre.is_empty() {
+        joined.push('-');
+        joined.push_str(version.pre.as_str());
+    }
+    if keep_suffix && !version.build.is_empty() {
+        joined.push('+');
+        joined.push_str(version.build.as_str());
+    }
+    joined
+}
+
+/// Substitutes the fields of a version into a template, e.g.
+/// `release-{major}.{minor}` becomes `release-1.2` for `1.2.3`.
+///
+/// The placeholders are `{version}`, `{major}`, `{minor}`, `{patch}`, `{pre}`
+/// and `{build}`, where the last two are empty if absent. `{{` and `}}` stand
+/// for literal braces.
+///
+/// # Arguments
+///
+/// - `template`: The template to substitute into.
+/// - `version`: The version to take the fields from.
+/// - `strict`: Whether unknown placeholders are an error instead of being
+///   kept as-is.
+///
+/// # Returns
+///
+/// The substituted string or [`Error::UnknownPlaceholder`] in strict mode.
+pub fn render_template(template: &str, version: &Version, strict: bool) -> Result<String, Error> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let Some(end) = tail.find('}').filter(|_| tail.starts_with('{')) else {
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+            continue;
+        };
+        let name = &tail[1..end];
+        match name {
+            "version" => out.push_str(&version.to_string()),
+            "major" => out.push_str(&version.major.to_string()),
+            "minor" => out.push_str(&version.minor.to_string()),
+            "patch" => out.push_str(&version.patch.to_string()),
+            "pre" => out.push_str(version.pre.as_str()),
+            "build" => out.push_str(version.build.as_str()),
+            _ if strict => {
+                return Err(Error::UnknownPlaceholder {
+                    name: name.to_string(),
+                })
+            }
+            _ => out.push_str(&tail[..=end]),
+        }
+        rest = &tail[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Normalizes all line endings of a string to LF and makes it end with exactly
+/// one newline.
+pub fn normalize_output(content: &str) -> String {
+    let mut normalized = content.replace("\r\n", "\n").replace('\r', "\n");
+    normalized.truncate(normalized.trim_end_matches('\n').len());
+    normalized.push('\n');
+    normalized
+}
+
+/// Writes the content of a `Cargo.toml` file, reporting a read-only file as
+/// [`Error::ReadOnlyManifest`].
+pub(crate) fn write_manifest(path: &Path, content: &str) -> Result<(), Error> {
+    let read_only = || Error::ReadOnlyManifest {
+        path: path.to_path_buf(),
+    };
+    // Checked up front since privileged users could otherwise write anyway.
+    if fs::metadata(path).is_ok_and(|m| m.permissions().readonly()) {
+        return Err(read_only());
+    }
+    fs::write(path, content).map_err(|e| match e.kind() {
+        ErrorKind::PermissionDenied => read_only(),
+        _ => Error::IoError(e),
+    })
+}
+
+/// Turns a document back into the content of a `Cargo.toml` file.
+fn render_manifest(doc: &Document, options: &WriteOptions) -> String {
+    let content = doc.to_string();
+    if options.normalize_output {
+        normalize_output(&content)
+    } else {
+        content
+    }
+}
+
+/// Overwrites the string inside a toml item while keeping the whitespace and
+/// comments around it, so that nothing but the value itself changes.
+fn set_version_item(item: &mut Item, version_str: &str) {
+    match item.as_value_mut() {
+        Some(existing) => set_version_value(existing, version_str),
+        None => *item = value(version_str),
+    }
+}
+
+/// Overwrites a toml value with a string while keeping its decor and quote
+/// style.
+pub(crate) fn set_version_value(existing: &mut Value, version_str: &str) {
+    let decor = existing.decor().clone();
+    let literal = match existing {
+        Value::String(s) => s
+            .as_repr()
+            .and_then(|repr| repr.as_raw().as_str())
+            .is_some_and(|raw| raw.starts_with('\'')),
+        _ => false,
+    };
+    *existing = match literal {
+        // Falls back to a basic string if the value can't be a literal one.
+        true => format!("'{version_str}'")
+            .parse()
+            .unwrap_or_else(|_| Value::from(version_str)),
+        false => Value::from(version_str),
+    };
+    *existing.decor_mut() = decor;
+}
+
+trait SemVerExt {
+    fn bump_major(&mut self);
+    fn bump_minor(&mut self);
+    fn bump_patch(&mut self);
+    fn clear_suffix(&mut self);
+}
+
+impl SemVerExt for Version {
+    fn bump_major(&mut self) {
+        self.major += 1;
+        self.minor = 0;
+        self.patch = 0;
+        self.clear_suffix();
+    }
+
+    fn bump_minor(&mut self) {
+        self.minor += 1;
+        self.patch = 0;
+        self.clear_suffix();
+    }
+
+    fn bump_patch(&mut self) {
+        self.patch += 1;
+        self.clear_suffix();
+    }
+
+    /// Removes the pre-release and build metadata, which describe the
+    /// previous release and don't carry over to a bumped one.
+    fn clear_suffix(&mut self) {
+        self.pre = Prerelease::EMPTY;
+        self.build = BuildMetadata::EMPTY;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        advance_prerelease, advance_toml_prerelease, bump_prerelease, bump_toml_version,
+        bump_toml_version_with_options, bump_version, bump_version_sequence, cargo_update,
+        check_downgrade,
+        clock::{DateTime, FixedClock},
+        compare_packages, compute_bumped_version, core_version, docker_tag, edit_version,
+        expect_package_name, finalize_prerelease, finalize_toml_prerelease, find_version,
+        get_package_info, get_package_version_str, get_release_history, get_version,
+        is_breaking_for, is_valid_version, join_version, normalize_output, open_prerelease,
+        open_toml_prerelease, parse_lenient_version, relabel_prerelease, relabel_toml_prerelease,
+        render_template, resolve_manifest_path, round_trip, scan_package_version,
+        set_build_metadata, set_version, set_version_parsed, set_version_with_options,
+        stamp_build_timestamp, stamp_release_date, start_prerelease, start_toml_prerelease,
+        validate_version_string, version_delta, version_to_json, with_build_metadata, Error,
+        Increment, LabelDelta, RoundTrip, VersionChange, WriteOptions,
+    };
+    use semver::Version;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn test_version_bump() {
+        const BASE_VERSION: &str = "0.1.0";
+        let mut v = bump_version(BASE_VERSION, Increment::Patch).unwrap();
+        assert_eq!(&v.to_string(), "0.1.1");
+        v = bump_version(&v.to_string(), Increment::Minor).unwrap();
+        assert_eq!(&v.to_string(), "0.2.0");
+        v = bump_version(&v.to_string(), Increment::Patch).unwrap();
+        v = bump_version(&v.to_string(), Increment::Patch).unwrap();
+        assert_eq!(&v.to_string(), "0.2.2");
+        v = bump_version(&v.to_string(), Increment::Major).unwrap();
+        assert_eq!(&v.to_string(), "1.0.0");
+        v = bump_version(&v.to_string(), Increment::Minor).unwrap();
+        assert_eq!(&v.to_string(), "1.1.0");
+        v = bump_version(&v.to_string(), Increment::Patch).unwrap();
+        assert_eq!(&v.to_string(), "1.1.1");
+    }
+
+    #[test]
+    fn test_prerelease_train() {
+        let mut v = start_prerelease("1.2.3", Increment::Minor, "rc").unwrap();
+        assert_eq!(&v.to_string(), "1.3.0-rc.1");
+        v = advance_prerelease(&v.to_string()).unwrap();
+        assert_eq!(&v.to_string(), "1.3.0-rc.2");
+        v = advance_prerelease(&v.to_string()).unwrap();
+        assert_eq!(&v.to_string(), "1.3.0-rc.3");
+        v = start_prerelease("1.3.0", Increment::Major, "alpha").unwrap();
+        assert_eq!(&v.to_string(), "2.0.0-alpha.1");
+        v = advance_prerelease("2.0.0-9").unwrap();
+        assert_eq!(&v.to_string(), "2.0.0-10");
+        v = advance_prerelease("2.0.0-rc.1+b1").unwrap();
+        assert_eq!(&v.to_string(), "2.0.0-rc.2+b1");
+
+        assert!(matches!(
+            advance_prerelease("1.3.0"),
+            Err(Error::NoPrereleaseCounter { .. })
+        ));
+        assert!(matches!(
+            advance_prerelease("1.3.0-rc"),
+            Err(Error::NoPrereleaseCounter { .. })
+        ));
+        assert!(start_prerelease("1.2.3", Increment::Patch, "r!c").is_err());
+
+        // Padded counters are numeric identifiers with leading zeros, which
+        // semver does not allow.
+        assert!(matches!(
+            advance_prerelease("1.3.0-rc.09"),
+            Err(Error::SemverParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_docker_tag() {
+        let v = Version::parse("1.2.3").unwrap();
+        assert_eq!(docker_tag("myrepo/app", &v, false), "myrepo/app:1.2.3");
+        let v = Version::parse("1.2.3-rc.1+build.42").unwrap();
+        assert_eq!(
+            docker_tag("myrepo/app", &v, false),
+            "myrepo/app:1.2.3-rc.1_build.42"
+        );
+        assert_eq!(docker_tag("myrepo/app", &v, true), "myrepo/app:1.2.3-rc.1");
+    }
+
+    #[test]
+    fn test_stamp_release_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nname = \"x\"\nversion = \"1.0.0\"\n").unwrap();
+
+        let clock = FixedClock(DateTime::from_unix(1_714_564_800));
+        assert_eq!(
+            stamp_release_date(&path, &clock, "%Y-%m-%d").unwrap(),
+            "2024-05-01"
+        );
+        let clock = FixedClock(DateTime::from_unix(1_717_243_200));
+        stamp_release_date(&path, &clock, "%d.%m.%Y").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[package]\nname = \"x\"\nversion = \"1.0.0\"\n\n\
+             [package.metadata.cargo-next]\nreleased = \"01.06.2024\"\n"
+        );
+    }
+
+    #[test]
+    fn test_version_validation() {
+        for valid in [
+            "0.0.0",
+            "1.2.3",
+            "1.2.3-rc.1",
+            "1.2.3+build.5",
+            "1.2.3-alpha+001",
+        ] {
+            assert!(is_valid_version(valid), "{valid}");
+            assert_eq!(&validate_version_string(valid).unwrap().to_string(), valid);
+        }
+        for invalid in [
+            "", "1", "1.2", "v1.2.3", "=1.2.3", "1.2.3.4", "01.2.3", "1.2.3-", " 1.2.3",
+        ] {
+            assert!(!is_valid_version(invalid), "{invalid}");
+            assert!(matches!(
+                validate_version_string(invalid),
+                Err(Error::SemverParseError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_normalize_output() {
+        assert_eq!(normalize_output("a = 1\r\nb = 2"), "a = 1\nb = 2\n");
+        assert_eq!(normalize_output("a = 1\rb = 2\r\n\r\n\n"), "a = 1\nb = 2\n");
+        assert_eq!(normalize_output("a = 1\n"), "a = 1\n");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\r\nversion = \"1.0.0\"\r\n\r\n\r\n").unwrap();
+        let options = WriteOptions {
+            normalize_output: true,
+            ..Default::default()
+        };
+        bump_toml_version_with_options(&path, Increment::Patch, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[package]\nversion = \"1.0.1\"\n"
+        );
+    }
+
+    #[test]
+    fn test_get_package_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(
+            &path,
+            "[package]\nname = \"x\"\nversion = \"1.2.3\"\nedition = \"2021\"\n\
+             rust-version = \"1.60\"\n",
+        )
+        .unwrap();
+        let info = get_package_info(&path).unwrap();
+        assert_eq!(info.name.as_deref(), Some("x"));
+        assert_eq!(info.version, Some(Version::new(1, 2, 3)));
+        assert_eq!(info.edition.as_deref(), Some("2021"));
+        assert_eq!(info.rust_version.as_deref(), Some("1.60"));
+        assert_eq!(
+            info.to_json(),
+            "{\"name\":\"x\",\"version\":\"1.2.3\",\"edition\":\"2021\",\"rust_version\":\"1.60\"}"
+        );
+
+        fs::write(&path, "[package]\nname = \"x\"\nedition.workspace = true\n").unwrap();
+        let info = get_package_info(&path).unwrap();
+        assert_eq!(info.name.as_deref(), Some("x"));
+        assert_eq!(info.version, None);
+        assert_eq!(info.edition, None);
+        assert_eq!(info.rust_version, None);
+
+        // A wrongly typed version is an error rather than no version.
+        fs::write(&path, "[package]\nname = \"x\"\nversion = 1\n").unwrap();
+        assert!(matches!(
+            get_package_info(&path),
+            Err(Error::InvalidFieldType { .. })
+        ));
+
+        // An inherited version gets resolved through the workspace.
+        let member = dir.path().join("member").join("Cargo.toml");
+        fs::create_dir_all(member.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            "[workspace]\nmembers = [\"member\"]\n\n[workspace.package]\nversion = \"2.0.1\"\n",
+        )
+        .unwrap();
+        fs::write(
+            &member,
+            "[package]\nname = \"member\"\nversion.workspace = true\nedition.workspace = true\n",
+        )
+        .unwrap();
+        let info = get_package_info(&member).unwrap();
+        assert_eq!(info.version, Some(Version::new(2, 0, 1)));
+        assert_eq!(info.edition, None);
+    }
+
+    #[test]
+    fn test_zero_version_guard() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.0.0\"\n").unwrap();
+
+        assert!(matches!(
+            set_version(&path, "0.0.0"),
+            Err(Error::ZeroVersion)
+        ));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[package]\nversion = \"1.0.0\"\n"
+        );
+        set_version(&path, "0.0.1").unwrap();
+
+        let options = WriteOptions {
+            allow_zero: true,
+            ..Default::default()
+        };
+        set_version_with_options(&path, "0.0.0", &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[package]\nversion = \"0.0.0\"\n"
+        );
+    }
+
+    #[test]
+    fn test_cargo_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src").join("lib.rs"), "").unwrap();
+        fs::write(
+            &path,
+            "[package]\nname = \"locked\"\nversion = \"1.0.0\"\n\n[workspace]\n",
+        )
+        .unwrap();
+
+        cargo_update(&path).unwrap();
+        set_version(&path, "1.1.0").unwrap();
+        cargo_update(&path).unwrap();
+        let lock = fs::read_to_string(dir.path().join("Cargo.lock")).unwrap();
+        assert!(lock.contains("name = \"locked\"\nversion = \"1.1.0\""));
+    }
+
+    #[test]
+    fn test_bump_version_sequence() {
+        use Increment::*;
+        let bump = |increments: &[Increment]| {
+            bump_version_sequence("1.2.5", increments)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(bump(&[]), "1.2.5");
+        assert_eq!(bump(&[Minor, Patch]), "1.3.1");
+        assert_eq!(bump(&[Patch, Minor]), "1.3.0");
+        assert_eq!(bump(&[Patch, Patch]), "1.2.7");
+        assert_eq!(bump(&[Major, Minor, Patch, Patch]), "2.1.2");
+    }
+
+    #[test]
+    fn test_get_release_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nname = \"x\"\nversion = \"1.0.0\"\n").unwrap();
+        assert!(get_release_history(&path).unwrap().is_empty());
+
+        let clock = FixedClock(DateTime::from_unix(1_714_564_800));
+        stamp_release_date(&path, &clock, "%Y-%m-%d").unwrap();
+        let history = get_release_history(&path).unwrap();
+        assert_eq!(
+            history.entries,
+            vec![("released".to_string(), "2024-05-01".to_string())]
+        );
+        assert_eq!(history.to_json(), "{\"released\":\"2024-05-01\"}");
+    }
+
+    #[test]
+    fn test_template_placeholder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        for placeholder in ["${VERSION}", "{{ version }}", "1.0.0-${PRE}"] {
+            fs::write(&path, format!("[package]\nversion = \"{placeholder}\"\n")).unwrap();
+            match get_version(&path) {
+                Err(Error::TemplatePlaceholder { value }) => assert_eq!(value, placeholder),
+                res => panic!("unexpected result {res:?}"),
+            }
+            assert!(matches!(
+                bump_toml_version_with_options(&path, Increment::Patch, &WriteOptions::default()),
+                Err(Error::TemplatePlaceholder { .. })
+            ));
+        }
+        fs::write(&path, "[package]\nversion = \"1.0\"\n").unwrap();
+        assert!(matches!(
+            get_version(&path),
+            Err(Error::SemverParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_prerelease_modes() {
+        assert_eq!(
+            &open_prerelease("1.2.3", "beta").unwrap().to_string(),
+            "1.2.3-beta.1"
+        );
+        assert_eq!(
+            &open_prerelease("1.2.3-rc.4", "beta").unwrap().to_string(),
+            "1.2.3-beta.1"
+        );
+        assert_eq!(
+            &finalize_prerelease("1.2.0-beta.3").unwrap().to_string(),
+            "1.2.0"
+        );
+        assert_eq!(
+            &finalize_prerelease("1.2.0-beta.3+b1").unwrap().to_string(),
+            "1.2.0+b1"
+        );
+        assert_eq!(&finalize_prerelease("1.2.0").unwrap().to_string(), "1.2.0");
+        assert_eq!(
+            &open_prerelease("1.2.3+b1", "rc").unwrap().to_string(),
+            "1.2.3-rc.1+b1"
+        );
+        assert!(open_prerelease("1.2.3", "").is_err());
+    }
+
+    #[test]
+    fn test_scan_package_version() {
+        let scan = scan_package_version;
+        assert_eq!(
+            scan("[package]\nname = \"x\"\nversion = \"1.2.3\"\n"),
+            Some("1.2.3")
+        );
+        assert_eq!(
+            scan("[ package ] # main\nversion='1.2.3' # pinned\n"),
+            Some("1.2.3")
+        );
+        assert_eq!(
+            scan("[dependencies]\nversion = \"9.9.9\"\n[package]\nversion = \"1.2.3\"\n"),
+            Some("1.2.3")
+        );
+        assert_eq!(
+            scan("[package.metadata]\nversion = \"9.9.9\"\n[package]\nversion = \"1.2.3\"\n"),
+            Some("1.2.3")
+        );
+        assert_eq!(scan("[package]\nversion-x = 1\nversion = \"1.2.3\""), None);
+
+        // Ambiguous layouts are left to the full parser.
+        assert_eq!(scan("package.version = \"1.2.3\"\n"), None);
+        assert_eq!(scan("package = { version = \"1.2.3\" }\n"), None);
+        assert_eq!(scan("[package]\nversion.workspace = true\n"), None);
+        assert_eq!(scan("[package]\nversion = \"1.2.\\u0033\"\n"), None);
+        assert_eq!(
+            scan("[package]\nname = \"x\"\n[lib]\nversion = \"1.2.3\"\n"),
+            None
+        );
+        assert_eq!(scan("[\"package\"]\nversion = \"1.2.3\"\n"), None);
+        assert_eq!(
+            scan("[badges]\ntext = \"\"\"\n[package]\n\"\"\"\n[package]\nversion = \"1.2.3\"\n"),
+            None
+        );
+        assert_eq!(scan("[package]\nversion = \"1.2.3\" trailing\n"), None);
+        assert_eq!(scan("[package]\nname = \"x\"\n"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        let content = "[package]\nname = \"x\"   # name\nversion =  \"1.0.0\" # pinned\n";
+        fs::write(&path, content).unwrap();
+        let rt = round_trip(&path).unwrap();
+        assert!(rt.is_identical());
+        assert!(rt.changed_lines().is_empty());
+
+        let rt = RoundTrip {
+            path: path.clone(),
+            original: "a\nb\nc\n".to_string(),
+            rewritten: "a\nB\nc\nd\n".to_string(),
+        };
+        assert!(!rt.is_identical());
+        assert_eq!(
+            rt.changed_lines(),
+            vec![(2, Some("b\n"), Some("B\n")), (4, None, Some("d\n"))]
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+
+        // An inherited version gets round-tripped through the workspace root.
+        let root = dir.path().join("root");
+        let member = root.join("member").join("Cargo.toml");
+        fs::create_dir_all(member.parent().unwrap()).unwrap();
+        let root_content = "[workspace]\n\n[workspace.package]\nversion = '1.0.0'  # shared\n";
+        fs::write(root.join("Cargo.toml"), root_content).unwrap();
+        fs::write(&member, "[package]\nversion.workspace = true\n").unwrap();
+        let rt = round_trip(&member).unwrap();
+        assert_eq!(rt.path, root.join("Cargo.toml"));
+        assert_eq!(rt.original, root_content);
+        assert!(rt.is_identical());
+
+        let mut permissions = fs::metadata(&rt.path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&rt.path, permissions).unwrap();
+        assert!(matches!(rt.write(), Err(Error::ReadOnlyManifest { .. })));
+    }
+
+    #[test]
+    fn test_core_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        for (version, core) in [
+            ("1.2.0-rc.3+build", "1.2.0"),
+            ("1.2.0+build", "1.2.0"),
+            ("0.3.1-alpha", "0.3.1"),
+            ("4.5.6", "4.5.6"),
+        ] {
+            fs::write(&path, format!("[package]\nversion = \"{version}\"\n")).unwrap();
+            assert_eq!(core_version(&path).unwrap().to_string(), core);
+        }
+    }
+
+    #[test]
+    fn test_read_only_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.0.0\"\n").unwrap();
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        let err = set_version(&path, "1.0.1").unwrap_err();
+        assert!(matches!(&err, Error::ReadOnlyManifest { path: p } if *p == path));
+        assert!(err.to_string().contains("check its permissions"));
+        assert_eq!(get_version(&path).unwrap().to_string(), "1.0.0");
+    }
+
+    #[test]
+    fn test_set_version_parsed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.0.0\" # current\n").unwrap();
+
+        let version = Version::parse("2.0.0-rc.1+sha.abc").unwrap();
+        set_version_parsed(&path, &version).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[package]\nversion = \"2.0.0-rc.1+sha.abc\" # current\n"
+        );
+        assert!(matches!(
+            set_version_parsed(&path, &Version::new(0, 0, 0)),
+            Err(Error::ZeroVersion)
+        ));
+    }
+
+    #[test]
+    fn test_relabel_prerelease() {
+        let relabel = |s| {
+            relabel_prerelease(s, "alpha", "beta")
+                .unwrap()
+                .map(|v| v.to_string())
+        };
+        assert_eq!(relabel("1.3.0-alpha.4").as_deref(), Some("1.3.0-beta.1"));
+        assert_eq!(relabel("1.3.0-alpha").as_deref(), Some("1.3.0-beta.1"));
+        assert_eq!(
+            relabel("1.3.0-alpha.4+sha.1").as_deref(),
+            Some("1.3.0-beta.1+sha.1")
+        );
+        assert_eq!(relabel("1.3.0-beta.2"), None);
+        assert_eq!(relabel("1.3.0-alpha.x.1"), None);
+        assert_eq!(relabel("1.3.0"), None);
+    }
+
+    #[test]
+    fn test_version_delta() {
+        let delta = |a, b| version_delta(&Version::parse(a).unwrap(), &Version::parse(b).unwrap());
+
+        assert_eq!(
+            delta("1.4.2", "2.0.0").to_string(),
+            "major +1, minor reset, patch reset"
+        );
+        assert_eq!(
+            delta("1.4.2", "1.4.5").to_string(),
+            "major unchanged, minor unchanged, patch +3"
+        );
+        // Going back to 0 without a more significant bump is no reset.
+        assert_eq!(
+            delta("2.3.1", "1.0.0").to_string(),
+            "major -1, minor -3, patch -1"
+        );
+        let d = delta("1.3.0-rc.2", "1.3.0");
+        assert_eq!(d.pre, LabelDelta::Removed("rc.2".to_string()));
+        assert_eq!(
+            d.to_string(),
+            "major unchanged, minor unchanged, patch unchanged, pre-release rc.2 removed"
+        );
+        assert_eq!(
+            delta("1.2.0+a", "1.3.0-rc.1+b").to_string(),
+            "major unchanged, minor +1, patch unchanged, pre-release rc.1 added, build a -> b"
+        );
+        assert_eq!(
+            delta("0.1.9", "0.2.0").to_json(),
+            "{\"major\":{\"old\":0,\"new\":0,\"change\":0,\"reset\":false},\
+             \"minor\":{\"old\":1,\"new\":2,\"change\":1,\"reset\":false},\
+             \"patch\":{\"old\":9,\"new\":0,\"change\":-9,\"reset\":true},\
+             \"pre\":{\"old\":null,\"new\":null},\"build\":{\"old\":null,\"new\":null}}"
+        );
+    }
+
+    #[test]
+    fn test_stamp_build_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.2.3-rc.1+sha.abc\"\n").unwrap();
+
+        let clock = FixedClock(DateTime::from_unix(1_714_564_805));
+        let version = stamp_build_timestamp(&path, &clock).unwrap();
+        assert_eq!(version.to_string(), "1.2.3-rc.1+20240501T120005Z");
+        assert_eq!(get_version(&path).unwrap(), version);
+    }
+
+    #[test]
+    fn test_expect_package_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(
+            &path,
+            "[package]\nname = \"my-crate\"\nversion = \"1.0.0\"\n",
+        )
+        .unwrap();
+
+        expect_package_name(&path, "my-crate").unwrap();
+        let err = expect_package_name(&path, "other").unwrap_err();
+        assert!(matches!(
+            &err,
+            Error::UnexpectedPackageName { actual, .. } if actual == "my-crate"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "expected the package \"other\", but found \"my-crate\""
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let editor = dir.path().join("editor.sh");
+        fs::write(&editor, "#!/bin/sh\nsed -i 's/-rc.1//' \"$1\"\n").unwrap();
+        fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+        let editor = editor.to_str().unwrap();
+
+        assert_eq!(edit_version(editor, "1.2.0-rc.1").unwrap(), "1.2.0");
+        assert_eq!(edit_version(editor, "1.3.0").unwrap(), "1.3.0");
+        assert_eq!(edit_version("true", "1.3.0").unwrap(), "1.3.0");
+        assert!(matches!(
+            edit_version("false", "1.3.0"),
+            Err(Error::EditorFailed { .. })
+        ));
+        assert!(matches!(
+            edit_version(" ", "1.3.0"),
+            Err(Error::EditorNotSet)
+        ));
+    }
+
+    #[test]
+    fn test_compare_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let left = dir.path().join("left.toml");
+        let right = dir.path().join("right.toml");
+        fs::write(
+            &left,
+            "[package]\nname = \"x\"\nversion = \"1.2.0+a\"\nedition = \"2018\"\n",
+        )
+        .unwrap();
+        fs::write(
+            &right,
+            "[package]\nname = \"x\"\nversion = \"1.2.0+b\"\nedition = \"2021\"\n\
+             rust-version = \"1.60\"\n",
+        )
+        .unwrap();
+
+        let comparison = compare_packages(&left, &right).unwrap();
+        assert_eq!(comparison.ordering(), Some(std::cmp::Ordering::Equal));
+        assert_eq!(
+            comparison.differences(),
+            [
+                ("edition", Some("2018"), Some("2021")),
+                ("rust-version", None, Some("1.60"))
+            ]
+        );
+        assert!(comparison.to_json().contains(
+            "\"ordering\":\"equal\",\"differences\":{\"edition\":{\"left\":\"2018\",\"right\":\"2021\"}"
+        ));
+
+        fs::write(&right, "[package]\nname = \"x\"\n").unwrap();
+        let comparison = compare_packages(&left, &right).unwrap();
+        assert_eq!(comparison.ordering(), None);
+        assert_eq!(comparison.differences(), [("edition", Some("2018"), None)]);
+    }
+
+    #[test]
+    fn test_downgrade_guard() {
+        let v = |s| Version::parse(s).unwrap();
+        let check =
+            |old, new, allow_reopen| check_downgrade(&v(old), &v(new), allow_reopen).is_ok();
+
+        assert!(check("1.0.0", "1.0.1", false));
+        assert!(check("1.0.0+a", "1.0.0+b", false));
+        assert!(!check("1.0.1", "1.0.0", false));
+        assert!(!check("1.0.0", "1.0.0-rc.4", false));
+
+        // Reopening requires the same release and a formerly final version.
+        assert!(check("1.0.0", "1.0.0-rc.4", true));
+        assert!(check("1.0.0+build", "1.0.0-rc.1", true));
+        assert!(!check("1.0.1", "1.0.0-rc.4", true));
+        assert!(!check("1.0.0-rc.5", "1.0.0-rc.4", true));
+        assert!(!check("1.1.0", "1.0.0", true));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.0.0\"\n").unwrap();
+        let mut options = WriteOptions {
+            forbid_downgrade: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            set_version_with_options(&path, "1.0.0-rc.4", &options),
+            Err(Error::Downgrade { .. })
+        ));
+        options.allow_prerelease_downgrade = true;
+        set_version_with_options(&path, "1.0.0-rc.4", &options).unwrap();
+        assert_eq!(get_version(&path).unwrap().to_string(), "1.0.0-rc.4");
+        // Without the guard, downgrades stay possible.
+        set_version(&path, "0.9.0").unwrap();
+    }
+
+    #[test]
+    fn test_render_template() {
+        let version = Version::parse("1.2.3-rc.1+sha.5").unwrap();
+        let render = |template| render_template(template, &version, false).unwrap();
+
+        assert_eq!(render("release-{major}.{minor}"), "release-1.2");
+        assert_eq!(render("v{version}"), "v1.2.3-rc.1+sha.5");
+        assert_eq!(render("{patch}|{pre}|{build}"), "3|rc.1|sha.5");
+        assert_eq!(render("{{major}} is {major}"), "{major} is 1");
+        assert_eq!(render("{nope} {major} {"), "{nope} 1 {");
+        assert_eq!(render("a}b{}"), "a}b{}");
+        assert_eq!(
+            render_template("{pre}", &Version::new(1, 0, 0), false).unwrap(),
+            ""
+        );
+
+        let err = render_template("{major}-{nope}", &version, true).unwrap_err();
+        assert!(matches!(&err, Error::UnknownPlaceholder { name } if name == "nope"));
+        assert_eq!(
+            err.to_string(),
+            "unknown placeholder {nope} in the template"
+        );
+    }
+
+    #[test]
+    fn test_is_breaking_for() {
+        let breaks =
+            |req, version| is_breaking_for(req, &Version::parse(version).unwrap()).unwrap();
+        assert!(!breaks("^1.2", "1.2.0"));
+        assert!(!breaks("^1.2", "1.9.3"));
+        assert!(breaks("^1.2", "2.0.0"));
+        assert!(breaks("^1.2", "1.1.9"));
+        assert!(breaks("^0.3", "0.4.0"));
+        assert!(breaks("~1.2", "1.3.0"));
+        assert!(breaks("^1.2", "1.3.0-rc.1"));
+        assert!(!breaks(">=1.3.0-rc.1", "1.3.0-rc.2"));
+        assert!(is_breaking_for("not a req", &Version::new(1, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_missing_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nname = \"x\"\n").unwrap();
+        assert!(matches!(
+            get_version(&path),
+            Err(Error::InvalidFieldType { field, .. }) if field == "version"
+        ));
+        assert!(matches!(
+            get_package_version_str(&path),
+            Err(Error::InvalidFieldType { .. })
+        ));
+        fs::write(&path, "[workspace]\n").unwrap();
+        assert!(get_version(&path).is_err());
+    }
+
+    #[test]
+    fn test_min_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.9.0\"\n").unwrap();
+        let options = WriteOptions {
+            min_version: Some(Version::new(2, 0, 0)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            bump_toml_version_with_options(&path, Increment::Minor, &options),
+            Err(Error::BelowMinimum { min, version })
+                if min == Version::new(2, 0, 0) && version == Version::new(1, 10, 0)
+        ));
+        assert!(set_version_with_options(&path, "2.0.0-rc.1", &options).is_err());
+        assert_eq!(get_version(&path).unwrap(), Version::new(1, 9, 0));
+
+        bump_toml_version_with_options(&path, Increment::Major, &options).unwrap();
+        set_version_with_options(&path, "2.0.0+build.1", &options).unwrap();
+        assert_eq!(get_version(&path).unwrap().to_string(), "2.0.0+build.1");
+    }
+
+    #[test]
+    fn test_join_version() {
+        let version = Version::parse("1.2.3-rc.1+sha.5").unwrap();
+        assert_eq!(join_version(&version, "_", false), "1_2_3");
+        assert_eq!(join_version(&version, "-", true), "1-2-3-rc.1+sha.5");
+        assert_eq!(join_version(&Version::new(4, 0, 1), "", true), "401");
+    }
+
+    #[test]
+    fn test_parse_lenient_version() {
+        let parse = |s| parse_lenient_version(s).unwrap().to_string();
+        assert_eq!(parse("1.02.3"), "1.2.3");
+        assert_eq!(parse("01.0.00-rc.1+007"), "1.0.0-rc.1+007");
+        assert_eq!(parse("1.2.3"), "1.2.3");
+        assert!(parse_lenient_version("1..3").is_err());
+        assert!(parse_lenient_version("1.2").is_err());
+    }
+
+    #[test]
+    fn test_prerelease_increments() {
+        let bump = |v, increment| bump_version(v, increment).unwrap().to_string();
+        let beta = || Increment::Pre("beta".to_string());
+        assert_eq!(bump("1.2.0", beta()), "1.2.0-beta.1");
+        assert_eq!(bump("1.2.0-beta.1", beta()), "1.2.0-beta.2");
+        assert_eq!(bump("1.2.0-beta", beta()), "1.2.0-beta.1");
+        assert_eq!(bump("1.2.0-beta.3", Increment::Finalize), "1.2.0");
+        assert_eq!(bump("1.2.0", Increment::Finalize), "1.2.0");
+        assert!(matches!(
+            bump_version("1.2.0-alpha.2", beta()),
+            Err(Error::PrereleaseLabelMismatch { expected, actual })
+                if expected == "beta" && actual == "alpha.2"
+        ));
+        assert!(matches!(
+            bump_prerelease("1.2.0-beta.x", "beta"),
+            Err(Error::PrereleaseLabelMismatch { .. })
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.2.0\"\n").unwrap();
+        bump_toml_version(&path, beta()).unwrap();
+        bump_toml_version(&path, beta()).unwrap();
+        assert_eq!(get_version(&path).unwrap().to_string(), "1.2.0-beta.2");
+        bump_toml_version(&path, Increment::Finalize).unwrap();
+        assert_eq!(get_version(&path).unwrap().to_string(), "1.2.0");
+    }
+
+    #[test]
+    fn test_resolve_manifest_path() {
+        assert_eq!(
+            resolve_manifest_path("crates/a"),
+            Path::new("crates/a/Cargo.toml")
+        );
+        assert_eq!(
+            resolve_manifest_path("crates/a/"),
+            Path::new("crates/a/Cargo.toml")
+        );
+        assert_eq!(
+            resolve_manifest_path("crates/a/Cargo.toml"),
+            Path::new("crates/a/Cargo.toml")
+        );
+        assert_eq!(
+            resolve_manifest_path("fixtures/other.toml"),
+            Path::new("fixtures/other.toml")
+        );
+    }
+
+    #[test]
+    fn test_inherited_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Cargo.toml");
+        let member = dir.path().join("crates/a/Cargo.toml");
+        fs::create_dir_all(member.parent().unwrap()).unwrap();
+        fs::write(
+            &root,
+            "[workspace]\nmembers = [\"crates/*\"]\n\n\
+             [workspace.package]\nversion = \"1.2.0\" # shared\n",
+        )
+        .unwrap();
+        let member_content = "[package]\nname = \"a\"\nversion.workspace = true\n";
+        fs::write(&member, member_content).unwrap();
+
+        assert_eq!(get_version(&member).unwrap(), Version::new(1, 2, 0));
+        assert_eq!(get_package_version_str(&member).unwrap(), "1.2.0");
+        bump_toml_version(&member, Increment::Minor).unwrap();
+        assert_eq!(fs::read_to_string(&member).unwrap(), member_content);
+        assert_eq!(
+            fs::read_to_string(&root).unwrap(),
+            "[workspace]\nmembers = [\"crates/*\"]\n\n\
+             [workspace.package]\nversion = \"1.3.0\" # shared\n",
+        );
+        assert_eq!(get_version(&member).unwrap(), Version::new(1, 3, 0));
+
+        fs::write(&root, "[workspace]\n").unwrap();
+        assert!(matches!(
+            set_version(&member, "2.0.0"),
+            Err(Error::InvalidFieldType { field, .. }) if field == "workspace.package.version"
+        ));
+
+        fs::remove_file(&root).unwrap();
+        assert!(matches!(
+            get_version(&member),
+            Err(Error::WorkspaceRootNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compute_bumped_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        let content = "[package]\nversion = \"1.2.3\"\n";
+        fs::write(&path, content).unwrap();
+        assert_eq!(
+            compute_bumped_version(&path, Increment::Minor).unwrap(),
+            Version::new(1, 3, 0)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        assert_eq!(
+            bump_toml_version(&path, Increment::Minor).unwrap().to,
+            Version::new(1, 3, 0)
+        );
+    }
+
+    #[test]
+    fn test_version_to_json() {
+        assert_eq!(
+            version_to_json(&Version::parse("1.2.3-rc.1").unwrap()),
+            "{\"major\":1,\"minor\":2,\"patch\":3,\"pre\":\"rc.1\",\"build\":null}"
+        );
+        assert_eq!(
+            version_to_json(&Version::parse("0.4.0+sha.5").unwrap()),
+            "{\"major\":0,\"minor\":4,\"patch\":0,\"pre\":null,\"build\":\"sha.5\"}"
+        );
+    }
+
+    #[test]
+    fn test_build_metadata() {
+        let bump = |v, increment| bump_version(v, increment).unwrap().to_string();
+        assert_eq!(bump("1.2.3+meta", Increment::Patch), "1.2.4");
+        assert_eq!(bump("1.2.3-rc.1+meta", Increment::Minor), "1.3.0");
+        assert_eq!(bump("1.2.3-rc.1", Increment::Major), "2.0.0");
+        let pre = || Increment::Pre("rc".to_string());
+        assert_eq!(bump("1.2.3-rc.1+meta", pre()), "1.2.3-rc.2");
+        assert_eq!(bump("1.2.3+meta", pre()), "1.2.3-rc.1");
+        assert_eq!(bump("1.2.3-rc.1+meta", Increment::Finalize), "1.2.3");
+
+        let with = |v, meta| with_build_metadata(v, meta).unwrap().to_string();
+        assert_eq!(with("1.4.2", "abc1234"), "1.4.2+abc1234");
+        assert_eq!(with("1.4.2-rc.1+old", "ci.7"), "1.4.2-rc.1+ci.7");
+        assert_eq!(with("1.4.2+old", ""), "1.4.2");
+        assert!(matches!(
+            with_build_metadata("1.4.2", "a_b"),
+            Err(Error::SemverParseError(_))
+        ));
+        assert!(with_build_metadata("1.4.2", "a..b").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.4.2-rc.1\"\n").unwrap();
+        set_build_metadata(&path, "abc1234", &WriteOptions::default()).unwrap();
+        assert_eq!(
+            get_version(&path).unwrap().to_string(),
+            "1.4.2-rc.1+abc1234"
+        );
+        assert!(set_build_metadata(&path, "not valid", &WriteOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_version_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.2.0\"\n").unwrap();
+
+        let change = set_version(&path, "1.3.0").unwrap();
+        assert_eq!(change.from, Some(Version::new(1, 2, 0)));
+        assert_eq!(change.to.to_string(), "1.3.0");
+        assert_eq!(change.to_string(), "1.2.0 -> 1.3.0");
+        let change = bump_toml_version(&path, Increment::Patch).unwrap();
+        assert_eq!(change.to_string(), "1.3.0 -> 1.3.1");
+
+        // Setting the same version again must not touch the file.
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions.clone()).unwrap();
+        let change = set_version(&path, "1.3.1").unwrap();
+        assert!(change.is_unchanged());
+        assert_eq!(change.to_string(), "unchanged: 1.3.1");
+        assert!(set_version(&path, "1.3.1+build").is_err());
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&path, permissions).unwrap();
+
+        fs::write(&path, "[package]\nversion = \"${VERSION}\"\n").unwrap();
+        let change = set_version(&path, "2.0.0").unwrap();
+        assert_eq!(change.from, None);
+        assert_eq!(change.to_string(), "2.0.0");
+        assert_eq!(
+            VersionChange {
+                from: Some(Version::new(2, 0, 0)),
+                to: Version::new(2, 0, 0),
+            }
+            .to_string(),
+            "unchanged: 2.0.0"
+        );
+    }
+
+    #[test]
+    fn test_prerelease_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        fs::write(&path, "[package]\nversion = \"1.2.0\"\n").unwrap();
+        let forbid = WriteOptions {
+            forbid_downgrade: true,
+            ..WriteOptions::default()
+        };
+
+        // Opening a pre-release of the current version is a downgrade.
+        assert!(matches!(
+            open_toml_prerelease(&path, "rc", &forbid),
+            Err(Error::Downgrade { .. })
+        ));
+        let change = start_toml_prerelease(&path, Increment::Minor, "rc", &forbid).unwrap();
+        assert_eq!(change.to_string(), "1.2.0 -> 1.3.0-rc.1");
+        let change = advance_toml_prerelease(&path, &forbid).unwrap();
+        assert_eq!(change.to_string(), "1.3.0-rc.1 -> 1.3.0-rc.2");
+        let change = relabel_toml_prerelease(&path, "rc", "beta", &WriteOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(change.to_string(), "1.3.0-rc.2 -> 1.3.0-beta.1");
+
+        let min = WriteOptions {
+            min_version: Some(Version::new(2, 0, 0)),
+            ..WriteOptions::default()
+        };
+        assert!(matches!(
+            finalize_toml_prerelease(&path, &min),
+            Err(Error::BelowMinimum { .. })
+        ));
+        assert!(matches!(
+            set_build_metadata(&path, "abc", &min),
+            Err(Error::BelowMinimum { .. })
+        ));
+        let change = finalize_toml_prerelease(&path, &WriteOptions::default()).unwrap();
+        assert_eq!(change.to_string(), "1.3.0-beta.1 -> 1.3.0");
+        assert_eq!(get_version(&path).unwrap(), Version::new(1, 3, 0));
+    }
+
+    #[test]
+    fn test_find_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cargo.toml");
+        assert_eq!(find_version(&path).unwrap(), None);
+        fs::write(&path, "[package]\nname = \"x\"\n").unwrap();
+        assert_eq!(find_version(&path).unwrap(), None);
+        fs::write(&path, "[package]\nversion = \"1.2.3\"\n").unwrap();
+        assert_eq!(find_version(&path).unwrap(), Some(Version::new(1, 2, 3)));
+
+        fs::write(&path, "[package]\nversion = 1\n").unwrap();
+        assert!(matches!(
+            find_version(&path),
+            Err(Error::InvalidFieldType { .. })
+        ));
+        fs::write(&path, "[package]\nversion = \"1.x\"\n").unwrap();
+        assert!(matches!(
+            find_version(&path),
+            Err(Error::SemverParseError(_))
+        ));
+        fs::write(&path, "[package\n").unwrap();
+        assert!(matches!(find_version(&path), Err(Error::ParseError(_))));
     }
 }
@@ -1,24 +1,606 @@
-use cargo_next::{bump_toml_version, get_version, set_version, Increment};
-use clap::{Parser, Subcommand};
+use cargo_next::{
+    advance_toml_prerelease, bump_version_sequence, cargo_update,
+    clock::SystemClock,
+    compare_packages, core_version,
+    deps::{update_examples, DependencyKind},
+    docker_tag, edit_version, expect_package_name, finalize_toml_prerelease, find_version,
+    get_package_info, get_package_name, get_package_version_str, get_release_history, get_version,
+    git::{
+        changed_files, changed_manifests, commit_messages, describe_version,
+        infer_increment_from_commits, DescribeMapping,
+    },
+    github::{append_github_output, GITHUB_OUTPUT},
+    ignore::IgnoreList,
+    is_breaking_for, join_version, open_toml_prerelease, parse_lenient_version,
+    relabel_toml_prerelease, render_template, render_version, resolve_manifest_path, round_trip,
+    set_version_with_options, stamp_build_timestamp, stamp_release_date, start_toml_prerelease,
+    validate_version_string, version_delta, version_manifest, version_to_json, with_build_metadata,
+    workspace::{
+        distinct_versions, find_member, find_workspace_root, group_by_version_manifest, lock_check,
+        workspace_members,
+    },
+    Increment, PackageComparison, PackageInfo, ReleaseHistory, WriteOptions,
+};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use semver::Version;
-use std::{env::current_dir, io, process::exit};
+use std::{
+    cmp::Ordering,
+    env::{self, current_dir},
+    io,
+    path::{Path, PathBuf},
+    process::exit,
+};
 
 #[derive(Debug, Parser)]
 #[clap(author, bin_name("cargo-next"), version)]
 struct Cli {
     /// This is because when we're called from cargo, our first arg is the command we were calld as.
     next: String,
+    /// The `Cargo.toml` file to use, or the directory containing it, instead
+    /// of the one in the current directory.
+    #[arg(long, global = true, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+    /// A shorthand used instead of a subcommand: `+major`, `+minor` or
+    /// `+patch` bumps like the subcommand of that name, anything else is a
+    /// version to set, e.g. `cargo next 1.2.3`. Subcommand names take
+    /// precedence, so `cargo next get` still prints the version.
+    #[arg(value_name = "SHORTHAND", value_parser = parse_shorthand)]
+    shorthand: Option<Shorthand>,
+}
+
+/// The shorthand that can be given instead of a subcommand.
+#[derive(Clone, Debug)]
+enum Shorthand {
+    Bump(IncrementArg),
+    Set(String),
+}
+
+impl Shorthand {
+    /// Returns the subcommand that the shorthand stands for, with all other
+    /// arguments at their defaults.
+    fn into_command(self) -> Commands {
+        let args = match self {
+            Shorthand::Bump(increment) => {
+                let name = increment
+                    .to_possible_value()
+                    .map(|v| v.get_name().to_string());
+                vec![name.unwrap_or_default()]
+            }
+            Shorthand::Set(version) => vec!["set".to_string(), version],
+        };
+        let cli = Cli::parse_from(
+            ["cargo-next", "next"]
+                .into_iter()
+                .map(String::from)
+                .chain(args),
+        );
+        // Parsing a subcommand always yields one.
+        cli.command.expect("no subcommand")
+    }
+}
+
+fn parse_shorthand(token: &str) -> Result<Shorthand, String> {
+    match token.strip_prefix('+') {
+        Some(increment) => IncrementArg::from_str(increment, true).map(Shorthand::Bump),
+        None => Ok(Shorthand::Set(token.to_string())),
+    }
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
-    Get,
+    /// Prints the current version.
+    Get(GetArgs),
+    Major(BumpArgs),
+    Minor(BumpArgs),
+    Patch(BumpArgs),
+    /// Applies several increments in order and writes the result once, e.g.
+    /// `bump minor patch` turns `1.2.5` into `1.3.1`.
+    ///
+    /// Every increment is applied, so `bump patch patch` increments the patch
+    /// version twice.
+    Bump {
+        #[arg(required = true)]
+        increments: Vec<IncrementArg>,
+        #[command(flatten)]
+        args: BumpArgs,
+    },
+    Set(SetArgs),
+    /// Bumps a version field and starts a new pre-release, e.g. `1.3.0-rc.1`.
+    ///
+    /// Deprecated in favor of bumping the field and running `pre --start`.
+    #[command(hide = true)]
+    StartPre {
+        increment: IncrementArg,
+        #[arg(long, default_value = "rc")]
+        label: String,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Advances the pre-release counter, e.g. `rc.1` to `rc.2`.
+    ///
+    /// Deprecated in favor of `pre --bump`.
+    #[command(hide = true)]
+    AdvancePre {
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Manages the pre-release of the current version. Exactly one of
+    /// `--start`, `--bump` or `--finalize` has to be given.
+    Pre {
+        #[command(flatten)]
+        mode: PreMode,
+        /// The label of the pre-release to start.
+        #[arg(long, default_value = "rc", conflicts_with_all = ["bump", "finalize"])]
+        label: String,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Replaces the build metadata of the version.
+    Build {
+        /// Uses the current UTC time like `+20240501T120000Z`.
+        #[arg(long, required = true)]
+        timestamp: bool,
+    },
+    /// Reports whether the next version breaks consumers with the given
+    /// requirement, e.g. `^1.2`. Exits with 1 if it does.
+    Breaks {
+        req: String,
+        /// Checks the version after these increments instead of the current
+        /// one.
+        #[arg(long = "bump", value_enum)]
+        increments: Vec<IncrementArg>,
+    },
+    /// Compares the versions and the package information of two manifests.
+    Compare {
+        left: PathBuf,
+        right: PathBuf,
+        #[arg(long, value_enum, default_value = "plain")]
+        format: OutputFormat,
+    },
+    /// Prints how every field changes between two versions, e.g.
+    /// `major +1, minor reset, patch reset`.
+    DeltaReport {
+        old: String,
+        new: String,
+        #[arg(long, value_enum, default_value = "plain")]
+        format: OutputFormat,
+    },
+    /// Prints the increment that the conventional commits since a base
+    /// revision imply, one of `major`, `minor`, `patch` or `none`. Nothing gets
+    /// changed.
+    Infer {
+        /// The git revision to compare against, e.g. `origin/main`.
+        #[arg(long)]
+        base: String,
+    },
+    /// Switches the pre-release label from `--from` to `--to` and resets the
+    /// counter, e.g. `1.3.0-alpha.4` becomes `1.3.0-beta.1`. Crates on another
+    /// label are skipped.
+    PromotePre {
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        /// Promotes every member of the workspace instead of only the current
+        /// crate.
+        #[arg(long)]
+        workspace: bool,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Checks that the version equals the given one. Exits with 1 if it
+    /// doesn't.
+    Expect {
+        expected: String,
+        /// Ignores leading zeros in the `x.y.z` fields, e.g. `1.02.3` equals
+        /// `1.2.3`.
+        #[arg(long)]
+        lenient: bool,
+    },
+    /// Checks that all workspace members share a single version, resolving
+    /// inherited versions. Exits with 1 if there are several.
+    LockstepCheck,
+    /// Checks that several workspace members share the same version.
+    ///
+    /// Exits with 1 if they differ. With `--fix`, all members get set to the
+    /// highest of their versions instead.
+    LockCheck {
+        /// The name of a workspace member to check.
+        #[arg(long = "package", required = true, num_args = 1)]
+        packages: Vec<String>,
+        /// Sets mismatching members to the highest version.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Writes the unchanged version back into the manifest in memory and
+    /// reports whether anything else would change. Exits with 1 if so.
+    #[command(hide = true)]
+    SelfCheck {
+        /// Writes the round-tripped manifest to disk.
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Sets the version to one patch above the highest version published to
+    /// the registry. Does nothing if the local version is already higher.
+    #[cfg(feature = "registry")]
+    CatchUp {
+        /// The URL of the sparse registry index.
+        #[arg(
+            long,
+            value_name = "INDEX_URL",
+            num_args = 0..=1,
+            default_value = cargo_next::registry::CRATES_IO_INDEX,
+            default_missing_value = cargo_next::registry::CRATES_IO_INDEX
+        )]
+        registry: String,
+    },
+    /// Prints the release information recorded in
+    /// `[package.metadata.cargo-next]`.
+    History {
+        #[arg(long, value_enum, default_value = "plain")]
+        format: OutputFormat,
+    },
+    /// Prints the version without pre-release and build metadata.
+    Core,
+    /// Prints the name, version, edition and rust-version of the package.
+    Info {
+        #[arg(long, value_enum, default_value = "plain")]
+        format: OutputFormat,
+    },
+}
+
+impl Commands {
+    /// Returns whether the command reads the manifest of the current crate,
+    /// as opposed to commands that take all of their inputs as arguments.
+    fn needs_manifest(&self) -> bool {
+        match self {
+            Commands::Get(args) => args.reads_manifest() && args.default.is_none(),
+            Commands::Compare { .. } | Commands::DeltaReport { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct PreMode {
+    /// Starts a new pre-release on the current version, e.g. `1.2.3` becomes
+    /// `1.2.3-rc.1`.
+    #[arg(long)]
+    start: bool,
+    /// Bumps the numeric counter of the pre-release, e.g. `rc.1` becomes
+    /// `rc.2`.
+    #[arg(long)]
+    bump: bool,
+    /// Removes the pre-release, e.g. `1.2.3-rc.2` becomes `1.2.3`.
+    #[arg(long)]
+    finalize: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    Plain,
+    Json,
+}
+
+#[derive(Debug, Args)]
+struct SetArgs {
+    version: Option<String>,
+    #[command(flatten)]
+    write: WriteArgs,
+    /// Prints the modified manifest instead of writing it.
+    #[arg(long)]
+    stdout: bool,
+    /// Prints the version instead of writing it.
+    #[arg(long, conflicts_with = "stdout")]
+    dry_run: bool,
+    /// Attaches this build metadata, e.g. a git short hash. Without a
+    /// version, it replaces the build metadata of the current one.
+    #[arg(long, value_name = "META")]
+    build: Option<String>,
+    /// Opens the new version in `$EDITOR` for review before setting it.
+    /// Without a version, editing starts from the current one.
+    #[arg(long)]
+    edit: bool,
+    /// Derives the version from `git describe --tags`, e.g. `v1.2.3-5-gabcdef`
+    /// becomes `1.2.3-5.gabcdef`.
+    #[arg(long, conflicts_with = "version")]
+    from_describe: bool,
+    /// Where the commits since the tag go with `--from-describe`.
+    #[arg(long, value_enum, default_value = "pre")]
+    describe_as: DescribeArg,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum DescribeArg {
+    /// `1.2.3-5.gabcdef`
+    Pre,
+    /// `1.2.3+5.gabcdef`
+    Build,
+}
+
+impl From<DescribeArg> for DescribeMapping {
+    fn from(arg: DescribeArg) -> Self {
+        match arg {
+            DescribeArg::Pre => DescribeMapping::Prerelease,
+            DescribeArg::Build => DescribeMapping::Build,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct WriteArgs {
+    /// Normalizes line endings to LF and ends the manifest with exactly one
+    /// newline instead of preserving its formatting.
+    #[arg(long)]
+    normalize_output: bool,
+    /// Allows writing the version 0.0.0, which usually indicates a mistake.
+    #[arg(long)]
+    allow_zero: bool,
+    /// Refuses to write a version lower than the current one.
+    #[arg(long)]
+    forbid_downgrade: bool,
+    /// Still allows reopening a pre-release of the current version with
+    /// `--forbid-downgrade`, e.g. going from `1.0.0` to `1.0.0-rc.4`.
+    #[arg(long)]
+    allow_prerelease_downgrade: bool,
+    /// Refuses to write a version lower than this one, e.g. a contractual
+    /// baseline.
+    #[arg(long, value_name = "VERSION")]
+    min_version: Option<Version>,
+}
+
+impl WriteArgs {
+    fn options(&self) -> WriteOptions {
+        WriteOptions {
+            normalize_output: self.normalize_output,
+            allow_zero: self.allow_zero,
+            forbid_downgrade: self.forbid_downgrade,
+            allow_prerelease_downgrade: self.allow_prerelease_downgrade,
+            min_version: self.min_version.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct BumpArgs {
+    #[command(flatten)]
+    tag: TagArgs,
+    #[command(flatten)]
+    write: WriteArgs,
+    /// Records the release date in `[package.metadata.cargo-next]`.
+    #[arg(long)]
+    stamp_date: bool,
+    /// The format of the recorded release date.
+    #[arg(long, default_value = "%Y-%m-%d", requires = "stamp_date")]
+    date_format: String,
+    /// Updates the requirements on this crate inside the sub-crates of
+    /// `examples/` and `benches/`.
+    #[arg(long)]
+    update_examples: bool,
+    /// The dependency sections that `--update-examples` updates.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "dependencies,dev-dependencies,build-dependencies",
+        requires = "update_examples"
+    )]
+    sync_sections: Vec<SectionArg>,
+    /// Runs `cargo update --package <name>` to refresh `Cargo.lock`.
+    #[arg(long)]
+    cargo_update: bool,
+    /// Only bumps the crates of the repository whose files changed since the
+    /// `--base` revision, instead of the crate in the current directory.
+    #[arg(long, requires = "base")]
+    changed_only: bool,
+    /// The git revision to compare against for `--changed-only`.
+    #[arg(long)]
+    base: Option<String>,
+    /// Appends `version` and `old_version` to the `$GITHUB_OUTPUT` file of
+    /// GitHub Actions. Outside of GitHub Actions, a warning is printed.
+    #[arg(long)]
+    github_output: bool,
+    /// Fails instead of warning if `$GITHUB_OUTPUT` is not set.
+    #[arg(long, requires = "github_output")]
+    require_ci: bool,
+    /// Refuses to bump unless the package has this name.
+    #[arg(long, value_name = "NAME", conflicts_with = "changed_only")]
+    expect_name: Option<String>,
+    /// Prints the modified manifest instead of writing it.
+    #[arg(
+        long,
+        conflicts_with_all = ["docker_tag", "stamp_date", "update_examples", "cargo_update", "changed_only", "github_output"],
+    )]
+    stdout: bool,
+    /// Prints the computed version instead of writing it.
+    #[arg(
+        long,
+        conflicts_with_all = ["stdout", "docker_tag", "stamp_date", "update_examples", "cargo_update", "github_output"],
+    )]
+    dry_run: bool,
+    /// Prints the dry run as `package.version: "1.2.3" -> "1.2.4"`.
+    #[arg(long, requires = "dry_run")]
+    oneline: bool,
+}
+
+#[derive(Debug, Args)]
+struct GetArgs {
+    /// Reads the version of a packaged `.crate` file instead of the manifest.
+    #[cfg(feature = "crate-file")]
+    #[arg(long, value_name = "PATH")]
+    crate_file: Option<PathBuf>,
+    /// Reads the version of a package from `cargo metadata --format-version 1`
+    /// output on stdin instead of the manifest.
+    #[cfg(feature = "metadata")]
+    #[cfg_attr(feature = "crate-file", arg(conflicts_with = "crate_file"))]
+    #[arg(long, value_name = "PACKAGE")]
+    from_metadata: Option<String>,
+    /// Prints the version through a template like `release-{major}.{minor}`.
+    /// Supported are `{version}`, `{major}`, `{minor}`, `{patch}`, `{pre}` and
+    /// `{build}`.
+    #[arg(long, conflicts_with = "docker_tag")]
+    template: Option<String>,
+    /// How the version gets printed.
+    #[arg(
+        long,
+        value_enum,
+        default_value = "plain",
+        conflicts_with_all = ["template", "docker_tag"]
+    )]
+    output: OutputFormat,
+    /// Joins the `x.y.z` fields with this separator, e.g. `1_2_3`. The
+    /// pre-release and build metadata are omitted.
+    #[arg(long, value_name = "SEP", conflicts_with_all = ["template", "docker_tag", "output"])]
+    sep: Option<String>,
+    /// Appends the pre-release and build metadata to the `--sep` output, e.g.
+    /// `1_2_3-rc.1`.
+    #[arg(long, requires = "sep")]
+    keep_suffix: bool,
+    /// Fails on unknown placeholders instead of keeping them.
+    #[arg(long, requires = "template")]
+    strict_template: bool,
+    /// Reports this version if the manifest or its version field is missing.
+    #[arg(long, value_name = "VERSION")]
+    default: Option<Version>,
+    /// Also reports the `--default` version if the manifest or its version is
+    /// malformed.
+    #[arg(long, requires = "default")]
+    tolerant: bool,
+    #[command(flatten)]
+    tag: TagArgs,
+    #[command(flatten)]
+    exit: ExitArgs,
+}
+
+impl GetArgs {
+    /// Returns whether the version is read from the manifest of the current
+    /// crate instead of another source.
+    fn reads_manifest(&self) -> bool {
+        #[cfg(feature = "crate-file")]
+        if self.crate_file.is_some() {
+            return false;
+        }
+        #[cfg(feature = "metadata")]
+        if self.from_metadata.is_some() {
+            return false;
+        }
+        true
+    }
+}
+
+/// Modes that report a property of the version through the exit code instead
+/// of printing it. At most one of them can be given.
+#[derive(Debug, Args)]
+#[group(multiple = false)]
+struct ExitArgs {
+    /// Exits with the major version, clamped to 254, and 255 if the version
+    /// can't be read.
+    #[arg(long)]
+    exit_major: bool,
+    /// Exits with 0 if the version is a pre-release, 1 otherwise and 2 if the
+    /// version can't be read.
+    #[arg(long)]
+    exit_is_prerelease: bool,
+    /// Exits with 0 if the version has build metadata, 1 otherwise and 2 if the
+    /// version can't be read.
+    #[arg(long)]
+    exit_has_build: bool,
+    /// Exits with 0 if the version is at least 1.0.0 and not a pre-release, 1
+    /// otherwise and 2 if the version can't be read.
+    #[arg(long)]
+    exit_is_stable: bool,
+}
+
+impl ExitArgs {
+    /// Returns the exit code for errors, which must not be mistaken for the
+    /// answer of an exit mode.
+    fn error_code(&self) -> i32 {
+        if self.exit_major {
+            255
+        } else if self.exit_is_prerelease || self.exit_has_build || self.exit_is_stable {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Returns the exit code for the version if an exit mode has been chosen.
+    fn exit_code(&self, version: &Version) -> Option<i32> {
+        let code = |success: bool| if success { 0 } else { 1 };
+        if self.exit_major {
+            Some(version.major.min(254) as i32)
+        } else if self.exit_is_prerelease {
+            Some(code(!version.pre.is_empty()))
+        } else if self.exit_has_build {
+            Some(code(!version.build.is_empty()))
+        } else if self.exit_is_stable {
+            Some(code(version.major >= 1 && version.pre.is_empty()))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct TagArgs {
+    /// Prints the version as a tag of the given Docker image, e.g.
+    /// `myrepo/app:1.2.3`.
+    #[arg(long, value_name = "IMAGE")]
+    docker_tag: Option<String>,
+    /// Drops the build metadata from the Docker tag instead of replacing `+`
+    /// with `_`.
+    #[arg(long, requires = "docker_tag")]
+    strip_build: bool,
+}
+
+impl TagArgs {
+    /// Prints the Docker image tag of the version if one has been requested.
+    fn print_docker_tag(&self, version: &Version) -> bool {
+        match &self.docker_tag {
+            Some(image) => {
+                println!("{}", docker_tag(image, version, self.strip_build));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SectionArg {
+    Dependencies,
+    DevDependencies,
+    BuildDependencies,
+}
+
+impl From<SectionArg> for DependencyKind {
+    fn from(arg: SectionArg) -> Self {
+        match arg {
+            SectionArg::Dependencies => DependencyKind::Normal,
+            SectionArg::DevDependencies => DependencyKind::Dev,
+            SectionArg::BuildDependencies => DependencyKind::Build,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum IncrementArg {
     Major,
     Minor,
     Patch,
-    Set { version: Option<String> },
+}
+
+impl From<IncrementArg> for Increment {
+    fn from(arg: IncrementArg) -> Self {
+        match arg {
+            IncrementArg::Major => Increment::Major,
+            IncrementArg::Minor => Increment::Minor,
+            IncrementArg::Patch => Increment::Patch,
+        }
+    }
 }
 
 fn read_stdin() -> Result<Option<String>, std::io::Error> {
@@ -31,42 +613,532 @@ fn read_stdin() -> Result<Option<String>, std::io::Error> {
     }
 }
 
+/// Reads the version that `get` reports, falling back to `--default`.
+fn read_version_or_default(path: &Path, args: &GetArgs) -> Result<Version, cargo_next::Error> {
+    let Some(default) = &args.default else {
+        return read_version(path, args);
+    };
+    let result = match args.reads_manifest() {
+        true => find_version(path).map(|version| version.unwrap_or_else(|| default.clone())),
+        false => read_version(path, args),
+    };
+    match result {
+        Err(_) if args.tolerant => Ok(default.clone()),
+        result => result,
+    }
+}
+
+/// Reads the version that `get` reports.
+#[cfg_attr(
+    not(any(feature = "crate-file", feature = "metadata")),
+    allow(unused_variables)
+)]
+fn read_version(path: &Path, args: &GetArgs) -> Result<Version, cargo_next::Error> {
+    #[cfg(feature = "crate-file")]
+    if let Some(crate_file) = &args.crate_file {
+        return cargo_next::crate_file::version_in_crate_file(crate_file);
+    }
+    #[cfg(feature = "metadata")]
+    if let Some(name) = &args.from_metadata {
+        let json = io::read_to_string(io::stdin())?;
+        return cargo_next::metadata::version_from_metadata(&json, name);
+    }
+    get_version(path)
+}
+
+fn print_package_info(info: &PackageInfo, format: OutputFormat) {
+    match format {
+        OutputFormat::Plain => {
+            let version = info.version.as_ref().map(Version::to_string);
+            for (key, value) in [
+                ("name", info.name.as_deref()),
+                ("version", version.as_deref()),
+                ("edition", info.edition.as_deref()),
+                ("rust-version", info.rust_version.as_deref()),
+            ] {
+                println!("{key}: {}", value.unwrap_or("(none)"));
+            }
+        }
+        OutputFormat::Json => println!("{}", info.to_json()),
+    }
+}
+
+fn print_package_comparison(comparison: &PackageComparison, format: OutputFormat) {
+    if let OutputFormat::Json = format {
+        println!("{}", comparison.to_json());
+        return;
+    }
+    let show = |value: Option<&str>| value.unwrap_or("(none)").to_string();
+    let left = comparison.left.version.as_ref().map(Version::to_string);
+    let right = comparison.right.version.as_ref().map(Version::to_string);
+    let relation = match comparison.ordering() {
+        Some(Ordering::Less) => "<",
+        Some(Ordering::Equal) => "=",
+        Some(Ordering::Greater) => ">",
+        None => "?",
+    };
+    println!(
+        "version: {} {relation} {}",
+        show(left.as_deref()),
+        show(right.as_deref())
+    );
+    for (key, left, right) in comparison.differences() {
+        println!("{key}: {} -> {}", show(left), show(right));
+    }
+}
+
+fn print_release_history(history: &ReleaseHistory, format: OutputFormat) {
+    match format {
+        OutputFormat::Plain if history.is_empty() => println!("no recorded history"),
+        OutputFormat::Plain => {
+            for (key, value) in &history.entries {
+                println!("{key}: {value}");
+            }
+        }
+        OutputFormat::Json => println!("{}", history.to_json()),
+    }
+}
+
+/// Switches the pre-release label of the crate, or of every workspace member,
+/// from `from` to `to` and reports what happened to each crate.
+fn promote_prerelease(
+    path: &Path,
+    from: &str,
+    to: &str,
+    workspace: bool,
+    options: &WriteOptions,
+) -> Result<(), cargo_next::Error> {
+    let manifests = match workspace {
+        true => workspace_members(find_workspace_root(path)?)?,
+        false => vec![path.to_path_buf()],
+    };
+    // Crates sharing an inherited version get promoted together.
+    for crates in group_by_version_manifest(&manifests)?.into_values() {
+        let promoted = relabel_toml_prerelease(&crates[0], from, to, options)?;
+        for manifest in &crates {
+            let name = get_package_name(manifest)?;
+            match &promoted {
+                Some(change) => println!("promoted {name}, {change}"),
+                None => println!("skipped {name}, not on {from}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_lock_check(path: &Path, packages: &[String], fix: bool) -> Result<bool, cargo_next::Error> {
+    let root = find_workspace_root(path)?;
+    let manifests = packages
+        .iter()
+        .map(|name| find_member(&root, name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let check = lock_check(&manifests, fix)?;
+    if check.is_locked() {
+        println!("in lockstep at {}", check.target);
+        return Ok(true);
+    }
+    for (manifest, version) in &check.mismatched {
+        match fix {
+            true => println!(
+                "set {} from {version} to {}",
+                manifest.display(),
+                check.target
+            ),
+            false => println!(
+                "{} is at {version}, expected {}",
+                manifest.display(),
+                check.target
+            ),
+        }
+    }
+    Ok(fix)
+}
+
+fn set(path: &Path, args: &SetArgs) -> Result<(), cargo_next::Error> {
+    let mut version = args.version.clone();
+    if args.from_describe {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        version = Some(describe_version(dir, args.describe_as.into())?.to_string());
+    }
+    if args.edit {
+        let editor = env::var("EDITOR").map_err(|_| cargo_next::Error::EditorNotSet)?;
+        let proposed = match version {
+            Some(version) => version,
+            None => get_package_version_str(path)?,
+        };
+        version = Some(edit_version(&editor, &proposed)?);
+    } else if version.is_none() && args.build.is_some() {
+        version = Some(get_package_version_str(path)?);
+    } else if version.is_none() {
+        version = read_stdin()?;
+    }
+    let Some(mut version) = version else {
+        return Err(cargo_next::Error::NoVersionGiven);
+    };
+    if let Some(build) = &args.build {
+        version = with_build_metadata(&version, build)?.to_string();
+    }
+    if args.stdout {
+        let (_, content) = render_version(path, version, &args.write.options())?;
+        print!("{content}");
+    } else if args.dry_run {
+        let (version, _) = render_version(path, version, &args.write.options())?;
+        println!("{version}");
+    } else {
+        println!(
+            "{}",
+            set_version_with_options(path, version, &args.write.options())?
+        );
+    }
+    Ok(())
+}
+
+fn bump(path: &Path, increments: &[Increment], args: &BumpArgs) -> Result<(), cargo_next::Error> {
+    let (true, Some(base)) = (args.changed_only, &args.base) else {
+        return bump_manifest(path, increments, args, None).map(drop);
+    };
+    // Git reports absolute paths, which a relative directory can't be stripped
+    // from.
+    let path = std::path::absolute(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let manifests = changed_manifests(&changed_files(dir, base)?, &IgnoreList::find(dir)?)?;
+    if manifests.is_empty() {
+        eprintln!("no crates changed since {base}");
+    }
+    // Crates sharing an inherited version get bumped once.
+    let changed: Vec<_> = manifests.keys().collect();
+    for (declaring, crates) in group_by_version_manifest(&changed)? {
+        let label = declaring.strip_prefix(dir).ok();
+        let Some(version) = bump_manifest(&crates[0], increments, args, label)? else {
+            continue;
+        };
+        for manifest in &crates[1..] {
+            update_crate(manifest, &version, args)?;
+        }
+        for manifest in &crates {
+            let files: Vec<_> = manifests[manifest]
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            eprintln!(
+                "bumped {} to {version}, changed: {}",
+                get_package_name(manifest)?,
+                files.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn bump_manifest(
+    path: &Path,
+    increments: &[Increment],
+    args: &BumpArgs,
+    label: Option<&Path>,
+) -> Result<Option<Version>, cargo_next::Error> {
+    if let Some(expected) = &args.expect_name {
+        expect_package_name(path, expected)?;
+    }
+    // Checked up front so that a missing `$GITHUB_OUTPUT` fails before writing.
+    let github_output = match env::var_os(GITHUB_OUTPUT) {
+        _ if !args.github_output => None,
+        None if args.require_ci => return Err(cargo_next::Error::GithubOutputNotSet),
+        None => {
+            eprintln!("warning: ${GITHUB_OUTPUT} is not set, skipping the outputs");
+            None
+        }
+        output => output,
+    };
+    let old_version = get_package_version_str(path)?;
+    let version = bump_version_sequence(&old_version, increments)?;
+    if args.stdout {
+        let (_, content) = render_version(path, version.to_string(), &args.write.options())?;
+        print!("{content}");
+        return Ok(None);
+    }
+    if args.dry_run {
+        let (version, _) = render_version(path, version.to_string(), &args.write.options())?;
+        let field = match version_manifest(path)? == path {
+            true => "package.version",
+            false => "workspace.package.version",
+        };
+        let line = match args.oneline {
+            true => format!("{field}: \"{old_version}\" -> \"{version}\""),
+            false => version.to_string(),
+        };
+        match label {
+            Some(label) => println!("{}: {line}", label.display()),
+            None => println!("{line}"),
+        }
+        return Ok(None);
+    }
+    let version = set_version_with_options(path, version.to_string(), &args.write.options())?.to;
+    update_crate(path, &version, args)?;
+    if let Some(output) = github_output {
+        append_github_output(
+            output,
+            &[
+                ("version", &version.to_string()),
+                ("old_version", &old_version),
+            ],
+        )?;
+    }
+    args.tag.print_docker_tag(&version);
+    Ok(Some(version))
+}
+
+/// Updates everything of a crate that depends on its freshly bumped version.
+fn update_crate(path: &Path, version: &Version, args: &BumpArgs) -> Result<(), cargo_next::Error> {
+    if args.stamp_date {
+        stamp_release_date(path, &SystemClock, &args.date_format)?;
+    }
+    if args.update_examples {
+        let name = get_package_name(path)?;
+        let root = path.parent().unwrap_or(Path::new("."));
+        let kinds: Vec<_> = args.sync_sections.iter().map(|&s| s.into()).collect();
+        for (manifest, changed) in update_examples(root, &name, version, &kinds)? {
+            for kind in changed {
+                eprintln!("updated [{kind}] of {}", manifest.display());
+            }
+        }
+    }
+    if args.cargo_update {
+        cargo_update(path)?;
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    let manifest_path = cli.manifest_path;
+    let command = match (cli.command, cli.shorthand) {
+        (Some(command), None) => command,
+        (None, Some(shorthand)) => shorthand.into_command(),
+        (Some(_), Some(_)) => Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "a shorthand can't be combined with a subcommand",
+            )
+            .exit(),
+        (None, None) => Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingSubcommand,
+                "a subcommand or shorthand is required",
+            )
+            .exit(),
+    };
 
     // Check if the current directory is actually a cargo project.
-    let cargo_project_dir_path = current_dir()?;
-    let cargo_toml_file_path = cargo_project_dir_path.join("Cargo.toml");
-    if !cargo_toml_file_path.exists() {
-        eprintln!("Not inside a cargo project folder!");
-        exit(1);
-    }
-
-    let res = match cli.command {
-        Commands::Get => {
-            let res = get_version(&cargo_toml_file_path);
-            if let Ok(version) = &res {
-                println!("{version}");
+    let cargo_toml_file_path = match manifest_path {
+        Some(path) => resolve_manifest_path(path),
+        None => current_dir()?.join("Cargo.toml"),
+    };
+    let cargo_project_dir_path = match cargo_toml_file_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let error_code = match &command {
+        Commands::Get(args) => args.exit.error_code(),
+        _ => 1,
+    };
+    if !cargo_toml_file_path.exists() && command.needs_manifest() {
+        eprintln!(
+            "Not inside a cargo project folder! {} does not exist.",
+            cargo_toml_file_path.display()
+        );
+        exit(error_code);
+    }
+
+    let res = match command {
+        Commands::Get(args) => {
+            read_version_or_default(&cargo_toml_file_path, &args).and_then(|version| {
+                if let Some(code) = args.exit.exit_code(&version) {
+                    exit(code);
+                }
+                if let Some(template) = &args.template {
+                    println!(
+                        "{}",
+                        render_template(template, &version, args.strict_template)?
+                    );
+                } else if let OutputFormat::Json = args.output {
+                    println!("{}", version_to_json(&version));
+                } else if let Some(sep) = &args.sep {
+                    println!("{}", join_version(&version, sep, args.keep_suffix));
+                } else if !args.tag.print_docker_tag(&version) {
+                    println!("{version}");
+                }
+                Ok(())
+            })
+        }
+        Commands::Set(args) => set(&cargo_toml_file_path, &args),
+        Commands::Major(args) => bump(&cargo_toml_file_path, &[Increment::Major], &args),
+        Commands::Minor(args) => bump(&cargo_toml_file_path, &[Increment::Minor], &args),
+        Commands::Patch(args) => bump(&cargo_toml_file_path, &[Increment::Patch], &args),
+        Commands::Bump { increments, args } => {
+            let increments: Vec<_> = increments.into_iter().map(Increment::from).collect();
+            bump(&cargo_toml_file_path, &increments, &args)
+        }
+        Commands::StartPre {
+            increment,
+            label,
+            write,
+        } => {
+            eprintln!("warning: start-pre is deprecated, bump the version and use `pre --start`");
+            start_toml_prerelease(
+                &cargo_toml_file_path,
+                increment.into(),
+                &label,
+                &write.options(),
+            )
+            .map(|change| println!("{change}"))
+        }
+        Commands::AdvancePre { write } => {
+            eprintln!("warning: advance-pre is deprecated, use `pre --bump`");
+            advance_toml_prerelease(&cargo_toml_file_path, &write.options())
+                .map(|change| println!("{change}"))
+        }
+        Commands::Pre { mode, label, write } => {
+            let options = write.options();
+            let res = if mode.start {
+                open_toml_prerelease(&cargo_toml_file_path, &label, &options)
+            } else if mode.bump {
+                advance_toml_prerelease(&cargo_toml_file_path, &options)
+            } else {
+                finalize_toml_prerelease(&cargo_toml_file_path, &options)
+            };
+            res.map(|change| println!("{change}"))
+        }
+        // `--timestamp` is the only way of building the metadata so far.
+        Commands::Build { timestamp: _ } => {
+            stamp_build_timestamp(&cargo_toml_file_path, &SystemClock).map(drop)
+        }
+        Commands::Breaks { req, increments } => {
+            let increments: Vec<_> = increments.into_iter().map(Increment::from).collect();
+            get_package_version_str(&cargo_toml_file_path)
+                .and_then(|current| bump_version_sequence(&current, &increments))
+                .and_then(|version| {
+                    if is_breaking_for(&req, &version)? {
+                        println!("{version} breaks {req}");
+                        exit(1);
+                    }
+                    println!("{version} satisfies {req}");
+                    Ok(())
+                })
+        }
+        Commands::Compare {
+            left,
+            right,
+            format,
+        } => compare_packages(&left, &right)
+            .map(|comparison| print_package_comparison(&comparison, format)),
+        Commands::DeltaReport { old, new, format } => {
+            validate_version_string(&old).and_then(|old| {
+                let delta = version_delta(&old, &validate_version_string(&new)?);
+                match format {
+                    OutputFormat::Plain => println!("{delta}"),
+                    OutputFormat::Json => println!("{}", delta.to_json()),
+                }
+                Ok(())
+            })
+        }
+        Commands::Infer { base } => {
+            commit_messages(&cargo_project_dir_path, &base).map(|messages| {
+                let increment = match infer_increment_from_commits(&messages) {
+                    Some(Increment::Major) => "major",
+                    Some(Increment::Minor) => "minor",
+                    Some(Increment::Patch) => "patch",
+                    Some(Increment::Pre(_) | Increment::Finalize) | None => "none",
+                };
+                println!("{increment}");
+            })
+        }
+        Commands::PromotePre {
+            from,
+            to,
+            workspace,
+            write,
+        } => promote_prerelease(
+            &cargo_toml_file_path,
+            &from,
+            &to,
+            workspace,
+            &write.options(),
+        ),
+        Commands::Expect { expected, lenient } => {
+            let parse = match lenient {
+                true => parse_lenient_version,
+                false => validate_version_string,
+            };
+            parse(&expected).and_then(|expected| {
+                let actual = parse(&get_package_version_str(&cargo_toml_file_path)?)?;
+                if actual != expected {
+                    eprintln!("expected the version {expected}, but found {actual}");
+                    exit(1);
+                }
+                Ok(())
+            })
+        }
+        Commands::LockstepCheck => find_workspace_root(&cargo_toml_file_path)
+            .and_then(distinct_versions)
+            .map(|versions| {
+                let versions: Vec<_> = versions.iter().map(Version::to_string).collect();
+                match versions.as_slice() {
+                    [version] => println!("in lockstep at {version}"),
+                    _ => {
+                        println!("drifting across {}", versions.join(", "));
+                        exit(1);
+                    }
+                }
+            }),
+        Commands::LockCheck { packages, fix } => {
+            match run_lock_check(&cargo_toml_file_path, &packages, fix) {
+                Ok(false) => exit(1),
+                res => res.map(drop),
             }
-            res
         }
-        Commands::Set { mut version } => {
-            if let None = version {
-                version = read_stdin()?;
+        Commands::SelfCheck { apply } => round_trip(&cargo_toml_file_path).and_then(|rt| {
+            if rt.is_identical() {
+                println!("round-trip is byte-identical");
+            } else {
+                println!("round-trip changes the manifest, please report this as a bug:");
+                for (line, original, rewritten) in rt.changed_lines() {
+                    if let Some(original) = original {
+                        println!("{line:>5} - {}", original.trim_end_matches('\n'));
+                    }
+                    if let Some(rewritten) = rewritten {
+                        println!("{line:>5} + {}", rewritten.trim_end_matches('\n'));
+                    }
+                }
             }
-            match version {
-                Some(v) => set_version(&cargo_toml_file_path, v),
-                None => Ok(Version::parse("0.0.0")?),
+            if apply {
+                rt.write()?;
             }
+            if !rt.is_identical() {
+                exit(1);
+            }
+            Ok(())
+        }),
+        #[cfg(feature = "registry")]
+        Commands::CatchUp { registry } => {
+            cargo_next::registry::catch_up(&cargo_toml_file_path, &registry).map(|version| {
+                match version {
+                    Some(version) => println!("caught up to {version}"),
+                    None => println!("already ahead of the registry"),
+                }
+            })
+        }
+        Commands::History { format } => get_release_history(&cargo_toml_file_path)
+            .map(|history| print_release_history(&history, format)),
+        Commands::Core => core_version(&cargo_toml_file_path).map(|version| println!("{version}")),
+        Commands::Info { format } => {
+            get_package_info(&cargo_toml_file_path).map(|info| print_package_info(&info, format))
         }
-        Commands::Major => bump_toml_version(&cargo_toml_file_path, Increment::Major),
-        Commands::Minor => bump_toml_version(&cargo_toml_file_path, Increment::Minor),
-        Commands::Patch => bump_toml_version(&cargo_toml_file_path, Increment::Patch),
     };
 
     if let Err(e) = res {
         eprintln!("{e}");
-        exit(1);
+        exit(error_code);
     }
 
     // // If no flag has been specified and no version, read from stdin.
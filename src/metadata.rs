@@ -0,0 +1,78 @@
+use crate::Error;
+use semver::Version;
+
+/// Returns the version of a package from the output of
+/// `cargo metadata --format-version 1`.
+///
+/// Since cargo reports resolved versions, this also covers packages that
+/// inherit their version from the workspace. If dependencies share the name
+/// of the package, the workspace member wins.
+///
+/// # Arguments
+///
+/// - `json`: The JSON that `cargo metadata` printed.
+/// - `name`: The name of the package.
+///
+/// # Returns
+///
+/// The version or [`Error::PackageNotInMetadata`] if no package has the name.
+pub fn version_from_metadata(json: &str, name: &str) -> Result<Version, Error> {
+    let metadata: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| Error::InvalidMetadata(e.to_string()))?;
+    let packages = metadata["packages"]
+        .as_array()
+        .ok_or_else(|| Error::InvalidMetadata("no packages were listed".to_string()))?;
+    let members = metadata["workspace_members"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let candidates: Vec<_> = packages
+        .iter()
+        .filter(|package| package["name"].as_str() == Some(name))
+        .collect();
+    let package = candidates
+        .iter()
+        .find(|package| members.contains(&package["id"]))
+        .or_else(|| candidates.first())
+        .ok_or_else(|| Error::PackageNotInMetadata {
+            name: name.to_string(),
+        })?;
+    let version = package["version"]
+        .as_str()
+        .ok_or_else(|| Error::InvalidMetadata(format!("the package {name:?} has no version")))?;
+    Ok(Version::parse(version)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{metadata::version_from_metadata, Error};
+
+    #[test]
+    fn test_version_from_metadata() {
+        let json = r#"{
+            "packages": [
+                {"name": "dep", "version": "0.3.1", "id": "dep 0.3.1"},
+                {"name": "app", "version": "1.0.0", "id": "app 1.0.0 (registry)"},
+                {"name": "app", "version": "2.1.0-rc.1", "id": "app 2.1.0-rc.1 (path)"}
+            ],
+            "workspace_members": ["app 2.1.0-rc.1 (path)"],
+            "version": 1
+        }"#;
+        assert_eq!(
+            version_from_metadata(json, "app").unwrap().to_string(),
+            "2.1.0-rc.1"
+        );
+        assert_eq!(
+            version_from_metadata(json, "dep").unwrap().to_string(),
+            "0.3.1"
+        );
+        assert!(matches!(
+            version_from_metadata(json, "missing"),
+            Err(Error::PackageNotInMetadata { name }) if name == "missing"
+        ));
+        assert!(matches!(
+            version_from_metadata("not json", "app"),
+            Err(Error::InvalidMetadata(_))
+        ));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{get_package_name, get_version, set_version, Error};
+use semver::Version;
+use std::path::Path;
+
+/// The sparse index of crates.io.
+pub const CRATES_IO_INDEX: &str = "https://index.crates.io";
+
+/// Returns the path of a crate's file inside a registry index, e.g.
+/// `ca/rg/cargo-next`.
+pub fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    }
+}
+
+/// Parses the versions out of a crate's file from a registry index. Each line
+/// of the file is a JSON object describing one published version.
+///
+/// Yanked versions are included since they can't be published again either.
+pub fn parse_index_versions(content: &str) -> Result<Vec<Version>, Error> {
+    let mut versions = Vec::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let entry: serde_json::Value =
+            serde_json::from_str(line).map_err(|e| Error::Registry(e.to_string()))?;
+        let vers = entry["vers"]
+            .as_str()
+            .ok_or_else(|| Error::Registry("index entry without a version".to_string()))?;
+        versions.push(Version::parse(vers)?);
+    }
+    Ok(versions)
+}
+
+/// Returns all versions of a crate that have been published to a registry.
+///
+/// # Arguments
+///
+/// - `index`: The URL of the sparse registry index, e.g. [`CRATES_IO_INDEX`].
+/// - `name`: The name of the crate.
+///
+/// # Returns
+///
+/// The published versions, which are empty if the crate has never been
+/// published, or an error if the registry could not be queried.
+pub fn published_versions(index: &str, name: &str) -> Result<Vec<Version>, Error> {
+    let url = format!("{}/{}", index.trim_end_matches('/'), index_path(name));
+    let response = match ureq::get(&url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Ok(Vec::new()),
+        Err(e) => return Err(Error::Registry(e.to_string())),
+    };
+    let content = response.into_string()?;
+    parse_index_versions(&content)
+}
+
+/// Returns the version one patch above the highest published version, or
+/// `None` if the local version is already higher than everything published.
+pub fn catch_up_version(local: &Version, published: &[Version]) -> Option<Version> {
+    let highest = published.iter().max()?;
+    if local > highest {
+        return None;
+    }
+    Some(Version::new(
+        highest.major,
+        highest.minor,
+        highest.patch + 1,
+    ))
+}
+
+/// Sets the version inside a `Cargo.toml` file to one patch above the highest
+/// version published to a registry, so that the next publish won't collide.
+///
+/// # Arguments
+///
+/// - `path`: The path to the `Cargo.toml` file.
+/// - `index`: The URL of the sparse registry index, e.g. [`CRATES_IO_INDEX`].
+///
+/// # Returns
+///
+/// The new version, or `None` if the local version is already ahead of the
+/// registry and nothing has been written.
+pub fn catch_up(path: impl AsRef<Path>, index: &str) -> Result<Option<Version>, Error> {
+    let name = get_package_name(path.as_ref())?;
+    let local = get_version(path.as_ref())?;
+    let published = published_versions(index, &name)?;
+
+    match catch_up_version(&local, &published) {
+        Some(version) => Ok(Some(set_version(path, version.to_string())?.to)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::{catch_up_version, index_path, parse_index_versions};
+    use semver::Version;
+
+    #[test]
+    fn test_index_path() {
+        assert_eq!(index_path("a"), "1/a");
+        assert_eq!(index_path("ab"), "2/ab");
+        assert_eq!(index_path("abc"), "3/a/abc");
+        assert_eq!(index_path("Cargo-Next"), "ca/rg/cargo-next");
+    }
+
+    #[test]
+    fn test_catch_up_version() {
+        let published = parse_index_versions(
+            "{\"name\":\"x\",\"vers\":\"1.2.0\",\"yanked\":false}\n\
+             {\"name\":\"x\",\"vers\":\"1.3.0-rc.1\",\"yanked\":false}\n\
+             {\"name\":\"x\",\"vers\":\"1.2.5\",\"yanked\":true}\n",
+        )
+        .unwrap();
+        assert_eq!(published.len(), 3);
+
+        let v = |s| Version::parse(s).unwrap();
+        assert_eq!(catch_up_version(&v("1.0.0"), &published), Some(v("1.3.1")));
+        assert_eq!(
+            catch_up_version(&v("1.3.0-rc.1"), &published),
+            Some(v("1.3.1"))
+        );
+        assert_eq!(catch_up_version(&v("1.3.0"), &published), None);
+        assert_eq!(catch_up_version(&v("1.0.0"), &[]), None);
+    }
+}
@@ -0,0 +1,436 @@
+use crate::{
+    get_package_name, get_version, ignore::IgnoreList, set_version, version_manifest, Error,
+};
+use semver::Version;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use toml_edit::{Document, Item};
+
+/// Returns the `Cargo.toml` file of the workspace a manifest belongs to.
+///
+/// The directory of the manifest and all of its ancestors are searched for a
+/// `Cargo.toml` file containing a `[workspace]` table.
+///
+/// # Arguments
+///
+/// - `path`: The path to a `Cargo.toml` file inside the workspace.
+///
+/// # Returns
+///
+/// The path to the root `Cargo.toml` file or [`Error::WorkspaceRootNotFound`]
+/// if no ancestor declares a workspace.
+pub fn find_workspace_root(path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    let path = path.as_ref();
+    // A relative path like `./Cargo.toml` has no ancestors to search.
+    let absolute = std::path::absolute(path)?;
+    let start = absolute.parent().unwrap_or(Path::new("."));
+    for dir in start.ancestors() {
+        let manifest = dir.join("Cargo.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        let doc = fs::read_to_string(&manifest)?.parse::<Document>()?;
+        if doc.contains_key("workspace") {
+            return Ok(manifest);
+        }
+    }
+    Err(Error::WorkspaceRootNotFound {
+        path: path.to_path_buf(),
+    })
+}
+
+/// Returns the `Cargo.toml` files of all members of a workspace.
+///
+/// The `workspace.members` globs get expanded and `workspace.exclude` is
+/// honored, as well as the closest [`IGNORE_FILE`](crate::ignore::IGNORE_FILE).
+/// The root manifest itself is included if it has a `[package]` table.
+///
+/// # Arguments
+///
+/// - `root`: The path to the root `Cargo.toml` file of the workspace.
+///
+/// # Returns
+///
+/// The sorted paths of the member manifests.
+pub fn workspace_members(root: impl AsRef<Path>) -> Result<Vec<PathBuf>, Error> {
+    let root = root.as_ref();
+    let root_dir = root.parent().unwrap_or(Path::new("."));
+    let doc = fs::read_to_string(root)?.parse::<Document>()?;
+    let workspace = doc.get("workspace");
+    let ignore = IgnoreList::find(root_dir)?;
+
+    let exclude: Vec<PathBuf> = string_array(workspace, "exclude")
+        .into_iter()
+        .map(|path| root_dir.join(path))
+        .collect();
+
+    let mut members = Vec::new();
+    if doc.contains_key("package") {
+        members.push(root.to_path_buf());
+    }
+    for pattern in string_array(workspace, "members") {
+        let pattern = root_dir.join(pattern);
+        let entries =
+            glob::glob(&pattern.to_string_lossy()).map_err(|e| Error::InvalidPattern {
+                pattern: e.msg.to_string(),
+            })?;
+        for dir in entries.filter_map(Result::ok) {
+            let manifest = dir.join("Cargo.toml");
+            if manifest.is_file() && !exclude.iter().any(|path| dir.starts_with(path)) {
+                members.push(manifest);
+            }
+        }
+    }
+
+    members.retain(|manifest| !ignore.is_ignored(manifest));
+    members.sort();
+    members.dedup();
+    Ok(members)
+}
+
+/// Returns the `Cargo.toml` file of the workspace member with the given
+/// package name.
+///
+/// # Arguments
+///
+/// - `root`: The path to the root `Cargo.toml` file of the workspace.
+/// - `name`: The name of the package.
+///
+/// # Returns
+///
+/// The path to the member manifest or [`Error::PackageNotFound`].
+pub fn find_member(root: impl AsRef<Path>, name: &str) -> Result<PathBuf, Error> {
+    for manifest in workspace_members(root)? {
+        if get_package_name(&manifest)? == name {
+            return Ok(manifest);
+        }
+    }
+    Err(Error::PackageNotFound {
+        name: name.to_string(),
+    })
+}
+
+/// Returns the distinct versions of all members of a workspace, with
+/// inherited versions resolved.
+///
+/// A single version means that the workspace is in lockstep.
+///
+/// # Arguments
+///
+/// - `root`: The path to the root `Cargo.toml` file of the workspace.
+///
+/// # Returns
+///
+/// The versions or [`Error::NoPackages`] if the workspace has no members.
+pub fn distinct_versions(root: impl AsRef<Path>) -> Result<BTreeSet<Version>, Error> {
+    let root = root.as_ref();
+    let members = workspace_members(root)?;
+    if members.is_empty() {
+        return Err(Error::NoPackages);
+    }
+    members.iter().map(get_version).collect()
+}
+
+/// The result of checking that several crates share the same version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockCheck {
+    /// The highest version amongst the crates, which all others should match.
+    pub target: Version,
+    /// The crates whose version differs from the target, together with that
+    /// version.
+    pub mismatched: Vec<(PathBuf, Version)>,
+}
+
+impl LockCheck {
+    /// Returns whether all crates share the same version.
+    pub fn is_locked(&self) -> bool {
+        self.mismatched.is_empty()
+    }
+}
+
+/// Checks that several crates share the same version and optionally aligns
+/// them.
+///
+/// If the versions differ, the highest one is the target that all other
+/// crates get set to when `fix` is enabled.
+///
+/// # Arguments
+///
+/// - `manifests`: The paths to the `Cargo.toml` files of the crates.
+/// - `fix`: Whether to set mismatching crates to the target version.
+///
+/// # Returns
+///
+/// The target version and the crates that did not match it before fixing, or
+/// [`Error::NoPackages`] if no manifests were given.
+pub fn lock_check(manifests: &[impl AsRef<Path>], fix: bool) -> Result<LockCheck, Error> {
+    let mut versions = Vec::with_capacity(manifests.len());
+    for manifest in manifests {
+        let manifest = manifest.as_ref();
+        versions.push((manifest.to_path_buf(), get_version(manifest)?));
+    }
+
+    let Some(target) = versions.iter().map(|(_, v)| v).max().cloned() else {
+        return Err(Error::NoPackages);
+    };
+    let mismatched: Vec<_> = versions.into_iter().filter(|(_, v)| *v != target).collect();
+
+    if fix {
+        let mismatched: Vec<_> = mismatched.iter().map(|(manifest, _)| manifest).collect();
+        // Setting the version through any crate of a group updates the file
+        // that declares it.
+        for crates in group_by_version_manifest(&mismatched)?.values() {
+            set_version(&crates[0], target.to_string())?;
+        }
+    }
+    Ok(LockCheck { target, mismatched })
+}
+
+/// Groups crates by the `Cargo.toml` file that declares their version, see
+/// [`version_manifest`].
+///
+/// Crates that inherit their version from the workspace share one group, so
+/// that the shared version gets changed once instead of once per crate.
+///
+/// # Returns
+///
+/// The declaring files together with their crates in the given order.
+pub fn group_by_version_manifest(
+    manifests: &[impl AsRef<Path>],
+) -> Result<BTreeMap<PathBuf, Vec<PathBuf>>, Error> {
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for manifest in manifests {
+        let manifest = manifest.as_ref();
+        groups
+            .entry(version_manifest(manifest)?)
+            .or_default()
+            .push(manifest.to_path_buf());
+    }
+    Ok(groups)
+}
+
+/// Returns the strings of an array inside an optional table.
+fn string_array<'a>(table: Option<&'a Item>, key: &str) -> Vec<&'a str> {
+    table
+        .and_then(|table| table.get(key))
+        .and_then(Item::as_array)
+        .map(|array| array.iter().filter_map(|value| value.as_str()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::{
+        get_version,
+        ignore::IGNORE_FILE,
+        workspace::{
+            distinct_versions, find_member, find_workspace_root, group_by_version_manifest,
+            lock_check, workspace_members,
+        },
+        Error,
+    };
+    use std::{fs, path::Path};
+
+    /// Creates a workspace with the given members and their versions.
+    pub(crate) fn create_workspace(root: &Path, members: &[(&str, &str)]) {
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/excluded\"]\n",
+        )
+        .unwrap();
+        for (name, version) in members.iter().chain(&[("excluded", "0.0.1")]) {
+            let dir = root.join("crates").join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("Cargo.toml"),
+                format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n"),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_workspace_members() {
+        let dir = tempfile::tempdir().unwrap();
+        create_workspace(dir.path(), &[("a", "1.0.0"), ("b", "1.1.0")]);
+        let member = dir.path().join("crates").join("a").join("Cargo.toml");
+
+        let root = find_workspace_root(&member).unwrap();
+        assert_eq!(root, dir.path().join("Cargo.toml"));
+        assert_eq!(find_workspace_root(&root).unwrap(), root);
+        assert_eq!(
+            workspace_members(&root).unwrap(),
+            vec![
+                member.clone(),
+                dir.path().join("crates").join("b").join("Cargo.toml")
+            ]
+        );
+        assert_eq!(find_member(&root, "a").unwrap(), member);
+        assert!(matches!(
+            find_member(&root, "excluded"),
+            Err(Error::PackageNotFound { .. })
+        ));
+
+        let outside = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            find_workspace_root(outside.path().join("Cargo.toml")),
+            Err(Error::WorkspaceRootNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lock_check() {
+        let dir = tempfile::tempdir().unwrap();
+        create_workspace(
+            dir.path(),
+            &[("a", "1.2.0"), ("b", "1.2.0"), ("c", "1.3.0")],
+        );
+        let root = dir.path().join("Cargo.toml");
+        let a = find_member(&root, "a").unwrap();
+        let b = find_member(&root, "b").unwrap();
+        let c = find_member(&root, "c").unwrap();
+
+        // Already equal.
+        let check = lock_check(&[&a, &b], false).unwrap();
+        assert!(check.is_locked());
+        assert_eq!(check.target.to_string(), "1.2.0");
+
+        // A mismatch that only gets reported.
+        let check = lock_check(&[&a, &c], false).unwrap();
+        assert!(!check.is_locked());
+        assert_eq!(check.target.to_string(), "1.3.0");
+        assert_eq!(get_version(&a).unwrap().to_string(), "1.2.0");
+
+        // Several mismatches get fixed by moving everything to the maximum.
+        let check = lock_check(&[&c, &a, &b], true).unwrap();
+        assert_eq!(check.mismatched.len(), 2);
+        for manifest in [&a, &b, &c] {
+            assert_eq!(get_version(manifest).unwrap().to_string(), "1.3.0");
+        }
+        assert!(lock_check(&[&a, &b, &c], false).unwrap().is_locked());
+    }
+
+    #[test]
+    fn test_ignore_file() {
+        let dir = tempfile::tempdir().unwrap();
+        create_workspace(dir.path(), &[("a", "1.0.0"), ("b", "1.1.0")]);
+        let root = dir.path().join("Cargo.toml");
+        fs::write(
+            &root,
+            "[workspace]\nmembers = [\"crates/*\", \"vendor/*\"]\n",
+        )
+        .unwrap();
+        let vendored = dir.path().join("vendor").join("dep");
+        fs::create_dir_all(&vendored).unwrap();
+        fs::write(
+            vendored.join("Cargo.toml"),
+            "[package]\nname = \"dep\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        assert_eq!(workspace_members(&root).unwrap().len(), 4);
+
+        fs::write(dir.path().join(IGNORE_FILE), "vendor/**\ncrates/excluded\n").unwrap();
+        let members = workspace_members(&root).unwrap();
+        assert_eq!(members.len(), 2);
+
+        // Aligning the whole workspace leaves the vendored crate alone.
+        lock_check(&members, true).unwrap();
+        assert_eq!(get_version(&members[0]).unwrap().to_string(), "1.1.0");
+        assert_eq!(
+            get_version(vendored.join("Cargo.toml"))
+                .unwrap()
+                .to_string(),
+            "0.1.0"
+        );
+    }
+
+    #[test]
+    fn test_distinct_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        create_workspace(dir.path(), &[("a", "1.2.0"), ("b", "1.2.0")]);
+        let root = dir.path().join("Cargo.toml");
+        let versions = |root| {
+            distinct_versions(root)
+                .unwrap()
+                .into_iter()
+                .map(|v| v.to_string())
+        };
+
+        assert_eq!(versions(&root).collect::<Vec<_>>(), ["1.2.0"]);
+
+        // An inherited version resolves to the one of the workspace.
+        let c = dir.path().join("crates").join("c");
+        fs::create_dir_all(&c).unwrap();
+        fs::write(
+            c.join("Cargo.toml"),
+            "[package]\nname = \"c\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+        fs::write(
+            &root,
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/excluded\"]\n\n\
+             [workspace.package]\nversion = \"1.3.0\"\n",
+        )
+        .unwrap();
+        assert_eq!(versions(&root).collect::<Vec<_>>(), ["1.2.0", "1.3.0"]);
+
+        // Template placeholders get reported instead of a parse error.
+        fs::write(
+            c.join("Cargo.toml"),
+            "[package]\nname = \"c\"\nversion = \"${VERSION}\"\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            distinct_versions(&root),
+            Err(Error::TemplatePlaceholder { .. })
+        ));
+
+        let empty = tempfile::tempdir().unwrap();
+        fs::write(empty.path().join("Cargo.toml"), "[workspace]\n").unwrap();
+        assert!(matches!(
+            distinct_versions(empty.path().join("Cargo.toml")),
+            Err(Error::NoPackages)
+        ));
+    }
+
+    #[test]
+    fn test_inheriting_members() {
+        let dir = tempfile::tempdir().unwrap();
+        create_workspace(
+            dir.path(),
+            &[("a", "1.2.0"), ("b", "1.2.0"), ("c", "1.3.0")],
+        );
+        let root = dir.path().join("Cargo.toml");
+        fs::write(
+            &root,
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/excluded\"]\n\n\
+             [workspace.package]\nversion = \"1.2.0\"\n",
+        )
+        .unwrap();
+        let member = |name| dir.path().join("crates").join(name).join("Cargo.toml");
+        for name in ["a", "b"] {
+            fs::write(
+                member(name),
+                format!("[package]\nname = \"{name}\"\nversion.workspace = true\n"),
+            )
+            .unwrap();
+        }
+
+        let groups = group_by_version_manifest(&[member("a"), member("c"), member("b")]).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&root], [member("a"), member("b")]);
+        assert_eq!(groups[&member("c")], [member("c")]);
+
+        let check = lock_check(&[member("a"), member("b"), member("c")], true).unwrap();
+        assert_eq!(check.mismatched.len(), 2);
+        assert_eq!(get_version(member("a")).unwrap().to_string(), "1.3.0");
+        assert_eq!(get_version(member("b")).unwrap().to_string(), "1.3.0");
+        assert!(!fs::read_to_string(&root).unwrap().contains("[package]"));
+        assert!(lock_check(&[member("a"), member("b"), member("c")], false)
+            .unwrap()
+            .is_locked());
+    }
+}
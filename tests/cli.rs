@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Runs `cargo next get` with extra arguments inside a directory and returns
+/// the exit code and stdout.
+fn get(dir: &Path, args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-next"))
+        .args(["next", "get"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_get_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let default = ["--default", "0.1.0"];
+    let tolerant = ["--default", "0.1.0", "--tolerant"];
+
+    // Neither a manifest nor a version.
+    assert_eq!(get(dir.path(), &default), (Some(0), "0.1.0\n".to_string()));
+    fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"x\"\n").unwrap();
+    assert_eq!(get(dir.path(), &default), (Some(0), "0.1.0\n".to_string()));
+
+    // A malformed version only falls back with `--tolerant`.
+    for malformed in ["version = 1", "version = \"1.x\""] {
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            format!("[package]\nname = \"x\"\n{malformed}\n"),
+        )
+        .unwrap();
+        assert_eq!(get(dir.path(), &default), (Some(1), String::new()));
+        assert_eq!(get(dir.path(), &tolerant), (Some(0), "0.1.0\n".to_string()));
+    }
+
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"x\"\nversion = \"1.2.3\"\n",
+    )
+    .unwrap();
+    assert_eq!(get(dir.path(), &tolerant), (Some(0), "1.2.3\n".to_string()));
+}
+
+#[test]
+fn test_get_exit_modes() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("Cargo.toml");
+    fs::write(&manifest, "[package]\nversion = \"2.0.0-rc.1\"\n").unwrap();
+    assert_eq!(get(dir.path(), &["--exit-is-prerelease"]).0, Some(0));
+    assert_eq!(get(dir.path(), &["--exit-is-stable"]).0, Some(1));
+    assert_eq!(get(dir.path(), &["--exit-major"]).0, Some(2));
+
+    // Errors must not look like a "no".
+    fs::write(&manifest, "[package]\nversion = \"2.x\"\n").unwrap();
+    for mode in [
+        "--exit-is-prerelease",
+        "--exit-has-build",
+        "--exit-is-stable",
+    ] {
+        assert_eq!(get(dir.path(), &[mode]).0, Some(2), "{mode}");
+    }
+    assert_eq!(get(dir.path(), &["--exit-major"]).0, Some(255));
+    fs::remove_file(&manifest).unwrap();
+    assert_eq!(get(dir.path(), &["--exit-is-stable"]).0, Some(2));
+    assert_eq!(get(dir.path(), &["--exit-major"]).0, Some(255));
+
+    // Major versions never collide with the error code.
+    fs::write(&manifest, "[package]\nversion = \"300.0.0\"\n").unwrap();
+    assert_eq!(get(dir.path(), &["--exit-major"]).0, Some(254));
+}
+
+#[test]
+fn test_sync_sections_requires_update_examples() {
+    let dir = tempfile::tempdir().unwrap();
+    let content = "[package]\nname = \"x\"\nversion = \"1.2.3\"\n";
+    fs::write(dir.path().join("Cargo.toml"), content).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_cargo-next"))
+        .args(["next", "patch", "--sync-sections", "dependencies"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap()
+        .status;
+    assert_eq!(status.code(), Some(2));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+        content
+    );
+}
+
+#[test]
+fn test_changed_only_relative_manifest_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    let vendored = dir.path().join("vendor").join("dep");
+    fs::create_dir_all(&vendored).unwrap();
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"app\"\nversion = \"1.0.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        vendored.join("Cargo.toml"),
+        "[package]\nname = \"dep\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join(".cargo-next-ignore"), "vendor/**\n").unwrap();
+    git(&["init", "-q"]);
+    git(&["add", "-A"]);
+    git(&["commit", "-q", "-m", "init"]);
+    fs::write(dir.path().join("lib.rs"), "").unwrap();
+    fs::write(vendored.join("lib.rs"), "").unwrap();
+    git(&["add", "-A"]);
+
+    for manifest_path in [".", "Cargo.toml"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-next"))
+            .args(["next", "--manifest-path", manifest_path, "patch"])
+            .args(["--changed-only", "--base", "HEAD", "--dry-run"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{manifest_path}");
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "Cargo.toml: 1.0.1\n",
+            "{manifest_path}"
+        );
+    }
+}
+
+#[test]
+fn test_set_empty_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let content = "[package]\nname = \"x\"\nversion = \"1.2.3\"\n";
+    fs::write(dir.path().join("Cargo.toml"), content).unwrap();
+    for args in [&["next", "set"][..], &["next", "set", "--allow-zero"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-next"))
+            .args(args)
+            .current_dir(dir.path())
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{args:?}");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+            content
+        );
+    }
+}
+
+#[test]
+fn test_stdout_conflicts_with_github_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let output_file = dir.path().join("github_output");
+    fs::write(
+        dir.path().join("Cargo.toml"),
+        "[package]\nname = \"x\"\nversion = \"1.2.3\"\n",
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-next"))
+        .args(["next", "patch", "--stdout", "--github-output"])
+        .env("GITHUB_OUTPUT", &output_file)
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(!output_file.exists());
+}
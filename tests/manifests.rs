@@ -0,0 +1,100 @@
+use cargo_next::{bump_toml_version, get_version, render_version, set_version, Increment};
+use std::{fs, path::PathBuf};
+use tempfile::TempDir;
+
+/// The fixtures together with the package version they declare.
+const FIXTURES: &[(&str, &str)] = &[
+    ("badges_first.toml", "0.3.1"),
+    ("features_first.toml", "2.7.0-beta.2"),
+    ("package_last.toml", "10.20.30"),
+    ("nested_metadata.toml", "1.0.0+build.5"),
+    ("dotted_package.toml", "0.4.2"),
+];
+
+/// Copies a fixture into a temporary directory and returns the directory, the
+/// path of the copied `Cargo.toml` and its original content.
+fn copy_fixture(name: &str) -> (TempDir, PathBuf, String) {
+    let content = fs::read_to_string(format!("tests/fixtures/{name}")).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Cargo.toml");
+    fs::write(&path, &content).unwrap();
+    (dir, path, content)
+}
+
+/// Returns the content of a fixture with only its package version replaced.
+fn expected_content(content: &str, old: &str, new: &str) -> String {
+    let old = format!("\"{old}\"");
+    assert_eq!(content.matches(&old).count(), 1, "ambiguous fixture");
+    content.replace(&old, &format!("\"{new}\""))
+}
+
+#[test]
+fn test_get_version_with_unusual_sections() {
+    for (name, version) in FIXTURES {
+        let (_dir, path, _) = copy_fixture(name);
+        assert_eq!(&get_version(&path).unwrap().to_string(), version, "{name}");
+    }
+}
+
+#[test]
+fn test_set_version_only_changes_version() {
+    for (name, version) in FIXTURES {
+        let (_dir, path, content) = copy_fixture(name);
+        set_version(&path, "4.5.6").unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            written,
+            expected_content(&content, version, "4.5.6"),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_bump_version_only_changes_version() {
+    let (_dir, path, content) = copy_fixture("package_last.toml");
+    bump_toml_version(&path, Increment::Minor).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    assert_eq!(written, expected_content(&content, "10.20.30", "10.21.0"));
+
+    let (_dir, path, content) = copy_fixture("badges_first.toml");
+    bump_toml_version(&path, Increment::Patch).unwrap();
+    bump_toml_version(&path, Increment::Patch).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    assert_eq!(written, expected_content(&content, "0.3.1", "0.3.3"));
+}
+
+#[test]
+fn test_set_version_keeps_dotted_package_keys() {
+    let (_dir, path, _) = copy_fixture("dotted_package.toml");
+    bump_toml_version(&path, Increment::Major).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    assert!(written.contains("package.version = \"1.0.0\" # keep in sync"));
+    assert!(!written.contains("[package]"));
+    assert_eq!(written.matches("version =").count(), 1);
+    assert_eq!(get_version(&path).unwrap().to_string(), "1.0.0");
+}
+
+#[test]
+fn test_set_version_keeps_comments_and_quote_style() {
+    let (_dir, path, content) = copy_fixture("aligned_columns.toml");
+    assert_eq!(get_version(&path).unwrap().to_string(), "1.0.0");
+    bump_toml_version(&path, Increment::Patch).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        content.replace("'1.0.0'", "'1.0.1'")
+    );
+}
+
+#[test]
+fn test_render_version_matches_written_content() {
+    for (name, _) in FIXTURES {
+        let (_dir, path, content) = copy_fixture(name);
+        let (version, rendered) = render_version(&path, "4.5.6", &Default::default()).unwrap();
+        assert_eq!(version.to_string(), "4.5.6");
+        assert_eq!(fs::read_to_string(&path).unwrap(), content, "{name}");
+
+        set_version(&path, "4.5.6").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), rendered, "{name}");
+    }
+}